@@ -0,0 +1,255 @@
+//! Content-defined chunking and cross-file chunk deduplication for file
+//! data, in the spirit of zvault's backup chunk store: instead of holding
+//! one contiguous buffer, `PyFile` holds an ordered list of [`ChunkHash`]es
+//! into a shared [`ChunkStore`], so identical chunks across files (or
+//! across overwrites of the same file) share one `Py<PyBytes>` and are
+//! only dropped once no file references them.
+//!
+//! Boundaries are found with a Buzhash-style rolling hash: a fixed-size
+//! window slides across the data maintaining a cheap-to-update fingerprint,
+//! and a chunk is cut whenever the fingerprint's low bits are all set,
+//! clamped to `[MIN_CHUNK, MAX_CHUNK]` so pathological input (all zeroes,
+//! adversarial data) can't produce degenerate chunk sizes.
+
+use pyo3::types::PyBytes;
+use pyo3::{Py, Python};
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::OnceLock;
+
+/// Rolling window size, in bytes.
+const WINDOW: usize = 48;
+/// Target average chunk size is `2**AVG_BITS` bytes (8 KiB).
+const AVG_BITS: u32 = 13;
+const MASK: u64 = (1 << AVG_BITS) - 1;
+const MIN_CHUNK: usize = 2 * 1024;
+const MAX_CHUNK: usize = 64 * 1024;
+
+/// A chunk's identity in [`ChunkStore`]: a `digest` built on `std`'s
+/// `SipHash` (avoiding a new dependency this crate doesn't otherwise have),
+/// plus a `collision_salt` that only ever becomes nonzero if two distinct
+/// chunks are found to share a digest. `ChunkStore::intern` always verifies
+/// the stored bytes against the new chunk before treating a digest match as
+/// a dedup hit, so a 64-bit digest collision costs a second (verified) slot
+/// instead of silently aliasing unrelated content.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ChunkHash {
+    digest: u64,
+    collision_salt: u32,
+}
+
+fn hash_bytes(data: &[u8]) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    data.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn buzhash_table() -> &'static [u64; 256] {
+    static TABLE: OnceLock<[u64; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = [0u64; 256];
+        for (i, slot) in table.iter_mut().enumerate() {
+            *slot = hash_bytes(&[i as u8, 0x5a, 0xa5]);
+        }
+        table
+    })
+}
+
+/// Split `data` into content-defined chunks. Boundaries only depend on
+/// local content (not file length or offset), so inserting or deleting
+/// bytes in the middle of a file only reshuffles the chunks touching the
+/// edit, not the whole file.
+fn split_chunks(data: &[u8]) -> Vec<&[u8]> {
+    if data.is_empty() {
+        return Vec::new();
+    }
+
+    let table = buzhash_table();
+    let mut chunks = Vec::new();
+    let mut start = 0usize;
+    let mut hash: u64 = 0;
+    let mut window = [0u8; WINDOW];
+    let mut window_len = 0usize;
+    let mut window_pos = 0usize;
+
+    for (i, &byte) in data.iter().enumerate() {
+        hash = hash.rotate_left(1) ^ table[byte as usize];
+        if window_len == WINDOW {
+            let outgoing = window[window_pos];
+            hash ^= table[outgoing as usize].rotate_left(WINDOW as u32);
+        } else {
+            window_len += 1;
+        }
+        window[window_pos] = byte;
+        window_pos = (window_pos + 1) % WINDOW;
+
+        let chunk_len = i - start + 1;
+        let at_boundary = window_len == WINDOW && (hash & MASK) == MASK;
+        if chunk_len >= MAX_CHUNK || (at_boundary && chunk_len >= MIN_CHUNK) {
+            chunks.push(&data[start..=i]);
+            start = i + 1;
+            hash = 0;
+            window_len = 0;
+            window_pos = 0;
+        }
+    }
+    if start < data.len() {
+        chunks.push(&data[start..]);
+    }
+    chunks
+}
+
+struct ChunkEntry {
+    data: Py<PyBytes>,
+    refcount: u64,
+}
+
+/// Dedup store for file content chunks, shared (via `Arc<Mutex<_>>`) by
+/// every `PyFile` belonging to the same `InodeTable`.
+pub struct ChunkStore {
+    chunks: HashMap<ChunkHash, ChunkEntry>,
+}
+
+impl ChunkStore {
+    pub fn new() -> Self {
+        Self {
+            chunks: HashMap::new(),
+        }
+    }
+
+    /// Split `data` into content-defined chunks, interning each one and
+    /// returning the ordered list of hashes a `PyFile` should keep.
+    pub fn split_and_intern(&mut self, py: Python<'_>, data: &[u8]) -> Vec<ChunkHash> {
+        split_chunks(data)
+            .into_iter()
+            .map(|chunk| self.intern(py, chunk))
+            .collect()
+    }
+
+    /// Look up or insert `data` as a chunk, bumping its refcount, and
+    /// return its hash. On a digest collision (two different `data` hashing
+    /// to the same `digest`), the existing entry's bytes are compared
+    /// against `data`; a mismatch tries the next `collision_salt` instead of
+    /// aliasing the two chunks together.
+    fn intern(&mut self, py: Python<'_>, data: &[u8]) -> ChunkHash {
+        let digest = hash_bytes(data);
+        let mut collision_salt = 0u32;
+        loop {
+            let hash = ChunkHash {
+                digest,
+                collision_salt,
+            };
+            match self.chunks.get_mut(&hash) {
+                Some(entry) if entry.data.bind(py).as_bytes() == data => {
+                    entry.refcount += 1;
+                    return hash;
+                }
+                Some(_) => collision_salt += 1,
+                None => {
+                    self.chunks.insert(
+                        hash,
+                        ChunkEntry {
+                            data: PyBytes::new(py, data).into(),
+                            refcount: 1,
+                        },
+                    );
+                    return hash;
+                }
+            }
+        }
+    }
+
+    /// Release one reference to each of `hashes`, dropping any chunk whose
+    /// refcount reaches zero. Called whenever a file's chunk list is
+    /// replaced (`write`, `truncate`) or the file itself is removed.
+    pub fn release(&mut self, hashes: &[ChunkHash]) {
+        for hash in hashes {
+            if let Some(entry) = self.chunks.get_mut(hash) {
+                entry.refcount -= 1;
+                if entry.refcount == 0 {
+                    self.chunks.remove(hash);
+                }
+            }
+        }
+    }
+
+    /// Fetch a chunk's bytes by hash. Panics only if `hash` isn't one this
+    /// store interned, which would indicate a bookkeeping bug elsewhere.
+    pub fn get(&self, hash: ChunkHash) -> &Py<PyBytes> {
+        &self
+            .chunks
+            .get(&hash)
+            .expect("chunk hash missing from store")
+            .data
+    }
+
+    pub fn len(&self, py: Python<'_>, hash: ChunkHash) -> usize {
+        self.get(hash).bind(py).as_bytes().len()
+    }
+}
+
+impl Default for ChunkStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interning_identical_bytes_reuses_the_chunk_and_balances_refcount() {
+        pyo3::prepare_freethreaded_python();
+        Python::attach(|py| {
+            let mut store = ChunkStore::new();
+            let a = store.intern(py, b"hello world");
+            let b = store.intern(py, b"hello world");
+            assert_eq!(a, b, "identical content must dedup to the same hash");
+            assert_eq!(store.chunks.len(), 1);
+
+            // Two references in, two releases out: the chunk should be gone.
+            store.release(&[a]);
+            assert_eq!(store.chunks.len(), 1, "still one live reference");
+            store.release(&[b]);
+            assert_eq!(store.chunks.len(), 0, "last reference released the chunk");
+        });
+    }
+
+    #[test]
+    fn distinct_chunks_never_alias_to_the_same_hash() {
+        pyo3::prepare_freethreaded_python();
+        Python::attach(|py| {
+            let mut store = ChunkStore::new();
+            let a = store.intern(py, b"chunk one");
+            let b = store.intern(py, b"chunk two");
+            assert_ne!(a, b);
+            assert_eq!(store.get(a).bind(py).as_bytes(), b"chunk one");
+            assert_eq!(store.get(b).bind(py).as_bytes(), b"chunk two");
+        });
+    }
+
+    #[test]
+    fn split_and_intern_round_trips_through_the_store() {
+        pyo3::prepare_freethreaded_python();
+        Python::attach(|py| {
+            let mut store = ChunkStore::new();
+            let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+            let hashes = store.split_and_intern(py, &data);
+            assert!(hashes.len() > 1, "input well above MAX_CHUNK should split");
+
+            let mut reassembled = Vec::with_capacity(data.len());
+            for hash in &hashes {
+                reassembled.extend_from_slice(store.get(*hash).bind(py).as_bytes());
+            }
+            assert_eq!(reassembled, data);
+
+            store.release(&hashes);
+            assert_eq!(
+                store.chunks.len(),
+                0,
+                "every chunk's refcount dropped to zero"
+            );
+        });
+    }
+}
@@ -0,0 +1,642 @@
+//! Append-only "docket" persistence format for `InodeTable`, modeled on
+//! Mercurial's dirstate-v2: a small fixed header (format version, `next_ino`,
+//! `uid`/`gid`, and pointers to the valid region) followed by a sequence of
+//! *generations*. Each generation is produced by one [`InodeTable::dump`]
+//! call and holds only the nodes that are new, changed, or removed (as a
+//! tombstone record) since the previous generation; unchanged nodes are
+//! decoded only to detect that they're unchanged and aren't re-written —
+//! their bytes from an earlier generation stay exactly where they are, and [`decode`] replays
+//! every generation in file order, later records overriding earlier ones
+//! for the same inode, to reconstruct the live tree. A trailing
+//! `link_counts` blob (small relative to file content, so rewritten each
+//! dump) follows the last generation. Readers must stop at `used_size` so
+//! that bytes appended past the recorded length (e.g. a stale tail left
+//! over from an aborted write) are ignored rather than corrupting the read.
+//!
+//! This doesn't reclaim space: a node that changes repeatedly leaves its
+//! earlier generations' bytes behind as unreferenced garbage in the file,
+//! same as dirstate-v2 before a "pack". Compaction isn't implemented here;
+//! a full rewrite via a fresh `dump` with no `previous` bytes is the only
+//! way to shrink the file back down.
+//!
+//! This is a distinct on-disk layout from [`crate::snapshot`]'s
+//! bincode+zstd format, aimed at warm-starting a tree without
+//! re-decompressing and re-parsing the whole thing up front.
+use crate::snapshot::{
+    Snapshot, SnapshotDeviceKind, SnapshotDir, SnapshotFile, SnapshotNode, SnapshotSpecial,
+    SnapshotSymlink,
+};
+use crate::tree::{Ino, ROOT_INO};
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind, Result as IoResult};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const MAGIC: &[u8; 4] = b"PFDK";
+const DOCKET_VERSION: u32 = 2;
+/// magic(4) + version(4) + next_ino(8) + uid(4) + gid(4) + used_size(8)
+/// + link_counts_offset(8) + link_counts_len(8)
+const HEADER_SIZE: usize = 4 + 4 + 8 + 4 + 4 + 8 + 8 + 8;
+/// ino(8) + kind(1) + special_kind(1) + mode(2) + uid(4) + gid(4) + rdev(4)
+/// + parent_ino(8) + atime(8) + mtime(8) + ctime(8) + size(8) + blob_offset(8) + blob_len(8)
+const RECORD_SIZE: usize = 8 + 1 + 1 + 2 + 4 + 4 + 4 + 8 + 8 + 8 + 8 + 8 + 8 + 8;
+/// Marks a record as a removal rather than a live node; only `ino` is
+/// meaningful, every other field is zeroed.
+const KIND_TOMBSTONE: u8 = 4;
+
+fn nanos_since_epoch(t: SystemTime) -> u64 {
+    t.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos() as u64
+}
+
+fn time_from_nanos(nanos: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_nanos(nanos)
+}
+
+fn io_err(e: impl std::fmt::Display) -> Error {
+    Error::new(ErrorKind::InvalidData, e.to_string())
+}
+
+#[derive(Default)]
+struct ByteWriter(Vec<u8>);
+
+impl ByteWriter {
+    fn u8(&mut self, v: u8) {
+        self.0.push(v);
+    }
+    fn u16(&mut self, v: u16) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+    fn u32(&mut self, v: u32) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+    fn u64(&mut self, v: u64) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+    /// A length-prefixed chunk within a record's blob slot.
+    fn chunk(&mut self, v: &[u8]) {
+        self.u32(v.len() as u32);
+        self.0.extend_from_slice(v);
+    }
+}
+
+struct ByteReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn need(&self, n: usize) -> IoResult<()> {
+        if self.pos + n > self.buf.len() {
+            Err(Error::new(ErrorKind::UnexpectedEof, "truncated docket"))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn u8(&mut self) -> IoResult<u8> {
+        self.need(1)?;
+        let v = self.buf[self.pos];
+        self.pos += 1;
+        Ok(v)
+    }
+    fn u16(&mut self) -> IoResult<u16> {
+        self.need(2)?;
+        let v = u16::from_le_bytes(self.buf[self.pos..self.pos + 2].try_into().unwrap());
+        self.pos += 2;
+        Ok(v)
+    }
+    fn u32(&mut self) -> IoResult<u32> {
+        self.need(4)?;
+        let v = u32::from_le_bytes(self.buf[self.pos..self.pos + 4].try_into().unwrap());
+        self.pos += 4;
+        Ok(v)
+    }
+    fn u64(&mut self) -> IoResult<u64> {
+        self.need(8)?;
+        let v = u64::from_le_bytes(self.buf[self.pos..self.pos + 8].try_into().unwrap());
+        self.pos += 8;
+        Ok(v)
+    }
+    fn chunk(&mut self) -> IoResult<Vec<u8>> {
+        let len = self.u32()? as usize;
+        self.need(len)?;
+        let v = self.buf[self.pos..self.pos + len].to_vec();
+        self.pos += len;
+        Ok(v)
+    }
+}
+
+/// A decoded header: every field needed to know where the generation
+/// stream and the trailing `link_counts` blob live within the file.
+struct Header {
+    next_ino: Ino,
+    uid: u32,
+    gid: u32,
+    used_size: usize,
+    link_counts_offset: usize,
+    link_counts_len: usize,
+}
+
+fn read_header(data: &[u8]) -> IoResult<Header> {
+    if data.len() < HEADER_SIZE || &data[0..4] != MAGIC {
+        return Err(Error::new(ErrorKind::InvalidData, "not a pyrofs docket file"));
+    }
+    let mut r = ByteReader::new(data);
+    r.pos = 4;
+    let version = r.u32()?;
+    if version != DOCKET_VERSION {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("unsupported docket version: {version}"),
+        ));
+    }
+    Ok(Header {
+        next_ino: r.u64()?,
+        uid: r.u32()?,
+        gid: r.u32()?,
+        used_size: (r.u64()? as usize).min(data.len()),
+        link_counts_offset: r.u64()? as usize,
+        link_counts_len: r.u64()? as usize,
+    })
+}
+
+/// One node's worth of fixed fields, shared by encode and decode so the
+/// field order can't drift between the two.
+#[allow(clippy::type_complexity)]
+fn node_record_fields(
+    node: &SnapshotNode,
+) -> (u8, u8, u16, u32, u32, u32, Ino, SystemTime, SystemTime, SystemTime, u64) {
+    match node {
+        SnapshotNode::File(f) => (
+            0, 0, f.mode, f.uid, f.gid, 0, f.parent_ino, f.atime, f.mtime, f.ctime,
+            f.content.len() as u64,
+        ),
+        SnapshotNode::Dir(d) => (
+            1, 0, d.mode, d.uid, d.gid, 0, d.parent_ino, d.atime, d.mtime, d.ctime,
+            d.children.len() as u64,
+        ),
+        SnapshotNode::Symlink(s) => (
+            2, 0, 0, s.uid, s.gid, 0, s.parent_ino, s.atime, s.mtime, s.ctime,
+            s.target.len() as u64,
+        ),
+        SnapshotNode::Special(sp) => {
+            let special_kind = match sp.kind {
+                SnapshotDeviceKind::NamedPipe => 0,
+                SnapshotDeviceKind::Socket => 1,
+                SnapshotDeviceKind::CharDevice => 2,
+                SnapshotDeviceKind::BlockDevice => 3,
+            };
+            (
+                3, special_kind, sp.mode, sp.uid, sp.gid, sp.rdev, sp.parent_ino, sp.atime, sp.mtime,
+                sp.ctime, 0,
+            )
+        }
+    }
+}
+
+/// Append the blob chunks (name, xattrs, content/children/target) for one
+/// node into `blob`, returning where they start within it.
+fn write_node_blob(blob: &mut ByteWriter, node: &SnapshotNode) -> u64 {
+    let start = blob.0.len() as u64;
+    match node {
+        SnapshotNode::File(f) => {
+            blob.chunk(f.name.as_bytes());
+            blob.chunk(&bincode::serialize(&f.xattrs).unwrap_or_default());
+            blob.chunk(&f.content);
+        }
+        SnapshotNode::Dir(d) => {
+            blob.chunk(d.name.as_bytes());
+            blob.chunk(&bincode::serialize(&d.xattrs).unwrap_or_default());
+            blob.chunk(&bincode::serialize(&d.children).unwrap_or_default());
+        }
+        SnapshotNode::Symlink(s) => {
+            blob.chunk(s.name.as_bytes());
+            blob.chunk(&bincode::serialize(&s.xattrs).unwrap_or_default());
+            blob.chunk(s.target.as_bytes());
+        }
+        SnapshotNode::Special(sp) => {
+            blob.chunk(sp.name.as_bytes());
+            blob.chunk(&bincode::serialize(&sp.xattrs).unwrap_or_default());
+            blob.chunk(&[]);
+        }
+    }
+    start
+}
+
+/// Encode one generation: a live record + blob chunk for every `(ino,
+/// node)` in `changed`, and a tombstone record for every ino in `removed`.
+/// Returns `None` if there's nothing to record (an unchanged save).
+fn encode_generation(changed: &[(Ino, &SnapshotNode)], removed: &[Ino]) -> Option<Vec<u8>> {
+    if changed.is_empty() && removed.is_empty() {
+        return None;
+    }
+
+    let mut records = ByteWriter::default();
+    let mut blob = ByteWriter::default();
+
+    for &(ino, node) in changed {
+        let blob_start = write_node_blob(&mut blob, node);
+        let blob_len = blob.0.len() as u64 - blob_start;
+        let (kind, special_kind, mode, uid, gid, rdev, parent_ino, atime, mtime, ctime, size) =
+            node_record_fields(node);
+        records.u64(ino);
+        records.u8(kind);
+        records.u8(special_kind);
+        records.u16(mode);
+        records.u32(uid);
+        records.u32(gid);
+        records.u32(rdev);
+        records.u64(parent_ino);
+        records.u64(nanos_since_epoch(atime));
+        records.u64(nanos_since_epoch(mtime));
+        records.u64(nanos_since_epoch(ctime));
+        records.u64(size);
+        records.u64(blob_start);
+        records.u64(blob_len);
+    }
+    for &ino in removed {
+        records.u64(ino);
+        records.u8(KIND_TOMBSTONE);
+        records.0.extend_from_slice(&[0u8; RECORD_SIZE - 9]);
+    }
+
+    let mut out = ByteWriter::default();
+    out.u64((changed.len() + removed.len()) as u64);
+    out.0.extend_from_slice(&records.0);
+    out.u64(blob.0.len() as u64);
+    out.0.extend_from_slice(&blob.0);
+    Some(out.0)
+}
+
+/// Replay every generation in `data[HEADER_SIZE..end]`, later records
+/// overriding earlier ones for the same inode, and tombstones removing it.
+fn replay_generations(data: &[u8], end: usize) -> IoResult<HashMap<Ino, SnapshotNode>> {
+    let mut nodes = HashMap::new();
+    let mut pos = HEADER_SIZE;
+    while pos < end {
+        let mut gen_header = ByteReader::new(&data[pos..end]);
+        let node_count = gen_header.u64()? as usize;
+        let records_start = pos + 8;
+        let records_len = node_count * RECORD_SIZE;
+        let blob_len_pos = records_start + records_len;
+        if blob_len_pos + 8 > end {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "docket generation header truncated"));
+        }
+        let blob_len = u64::from_le_bytes(data[blob_len_pos..blob_len_pos + 8].try_into().unwrap())
+            as usize;
+        let blob_start = blob_len_pos + 8;
+        let blob_end = blob_start + blob_len;
+        if blob_end > end {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "docket generation blob truncated"));
+        }
+        let blob = &data[blob_start..blob_end];
+
+        let mut records = ByteReader::new(&data[records_start..blob_len_pos]);
+        for _ in 0..node_count {
+            let ino = records.u64()?;
+            let kind = records.u8()?;
+            let special_kind = records.u8()?;
+            let mode = records.u16()?;
+            let node_uid = records.u32()?;
+            let node_gid = records.u32()?;
+            let rdev = records.u32()?;
+            let parent_ino = records.u64()?;
+            let atime = time_from_nanos(records.u64()?);
+            let mtime = time_from_nanos(records.u64()?);
+            let ctime = time_from_nanos(records.u64()?);
+            let _size = records.u64()?;
+            let blob_offset = records.u64()? as usize;
+            let blob_len = records.u64()? as usize;
+
+            if kind == KIND_TOMBSTONE {
+                nodes.remove(&ino);
+                continue;
+            }
+
+            let blob_entry_end = match blob_offset.checked_add(blob_len) {
+                Some(e) if e <= blob.len() => e,
+                _ => {
+                    return Err(Error::new(ErrorKind::UnexpectedEof, "docket blob entry out of range"));
+                }
+            };
+            let mut entry = ByteReader::new(&blob[blob_offset..blob_entry_end]);
+            let name = String::from_utf8(entry.chunk()?).map_err(io_err)?;
+            let xattr_bytes = entry.chunk()?;
+            let xattrs: HashMap<String, Vec<u8>> = if xattr_bytes.is_empty() {
+                HashMap::new()
+            } else {
+                bincode::deserialize(&xattr_bytes).map_err(io_err)?
+            };
+            let extra = entry.chunk()?;
+
+            let node = match kind {
+                0 => SnapshotNode::File(SnapshotFile {
+                    name,
+                    content: extra,
+                    mode,
+                    uid: node_uid,
+                    gid: node_gid,
+                    xattrs,
+                    parent_ino,
+                    atime,
+                    mtime,
+                    ctime,
+                }),
+                1 => {
+                    let children: HashMap<String, Ino> = if extra.is_empty() {
+                        HashMap::new()
+                    } else {
+                        bincode::deserialize(&extra).map_err(io_err)?
+                    };
+                    SnapshotNode::Dir(SnapshotDir {
+                        name,
+                        mode,
+                        uid: node_uid,
+                        gid: node_gid,
+                        xattrs,
+                        parent_ino,
+                        children,
+                        atime,
+                        mtime,
+                        ctime,
+                    })
+                }
+                2 => {
+                    let target = String::from_utf8(extra).map_err(io_err)?;
+                    SnapshotNode::Symlink(SnapshotSymlink {
+                        name,
+                        target,
+                        uid: node_uid,
+                        gid: node_gid,
+                        xattrs,
+                        parent_ino,
+                        atime,
+                        mtime,
+                        ctime,
+                    })
+                }
+                3 => {
+                    let device_kind = match special_kind {
+                        0 => SnapshotDeviceKind::NamedPipe,
+                        1 => SnapshotDeviceKind::Socket,
+                        2 => SnapshotDeviceKind::CharDevice,
+                        3 => SnapshotDeviceKind::BlockDevice,
+                        other => {
+                            return Err(Error::new(
+                                ErrorKind::InvalidData,
+                                format!("unknown docket special-file kind: {other}"),
+                            ));
+                        }
+                    };
+                    SnapshotNode::Special(SnapshotSpecial {
+                        name,
+                        kind: device_kind,
+                        mode,
+                        uid: node_uid,
+                        gid: node_gid,
+                        rdev,
+                        xattrs,
+                        parent_ino,
+                        atime,
+                        mtime,
+                        ctime,
+                    })
+                }
+                other => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("unknown docket node kind: {other}"),
+                    ));
+                }
+            };
+            nodes.insert(ino, node);
+        }
+        pos = blob_end;
+    }
+    Ok(nodes)
+}
+
+/// Encode `snapshot` as an append-only docket. When `previous` holds the
+/// raw bytes of a docket produced by an earlier `encode` call for the same
+/// tree, only nodes that are new, changed, or removed since then are
+/// written: their bytes become one new generation appended after
+/// `previous`'s existing generations, which are replayed only to find what
+/// changed and are otherwise carried forward verbatim (never re-serialized).
+/// A `None` `previous` (or one that fails to parse) falls back to a single
+/// from-scratch generation holding every node, exactly like a fresh dump.
+pub(crate) fn encode(snapshot: &Snapshot, previous: Option<&[u8]>) -> Vec<u8> {
+    let prior = previous.and_then(|data| {
+        let header = read_header(data).ok()?;
+        if header.link_counts_offset < HEADER_SIZE || header.link_counts_offset > header.used_size {
+            return None;
+        }
+        let nodes = replay_generations(data, header.link_counts_offset).ok()?;
+        Some((data, header, nodes))
+    });
+
+    let mut kept_generations: Vec<u8> = Vec::new();
+    let mut prev_nodes: HashMap<Ino, SnapshotNode> = HashMap::new();
+    if let Some((data, header, nodes)) = prior {
+        kept_generations.extend_from_slice(&data[HEADER_SIZE..header.link_counts_offset]);
+        prev_nodes = nodes;
+    }
+
+    let changed: Vec<(Ino, &SnapshotNode)> = snapshot
+        .nodes
+        .iter()
+        .filter_map(|(&ino, node)| {
+            if prev_nodes.get(&ino) == Some(node) {
+                None
+            } else {
+                Some((ino, node))
+            }
+        })
+        .collect();
+    let removed: Vec<Ino> = prev_nodes
+        .keys()
+        .filter(|&ino| !snapshot.nodes.contains_key(ino))
+        .copied()
+        .collect();
+
+    if let Some(generation) = encode_generation(&changed, &removed) {
+        kept_generations.extend_from_slice(&generation);
+    }
+
+    let link_counts = bincode::serialize(&snapshot.link_counts).unwrap_or_default();
+    let link_counts_offset = HEADER_SIZE + kept_generations.len();
+    let used_size = link_counts_offset + link_counts.len();
+
+    let mut out = ByteWriter::default();
+    out.0.extend_from_slice(MAGIC);
+    out.u32(DOCKET_VERSION);
+    out.u64(snapshot.next_ino);
+    out.u32(snapshot.uid);
+    out.u32(snapshot.gid);
+    out.u64(used_size as u64);
+    out.u64(link_counts_offset as u64);
+    out.u64(link_counts.len() as u64);
+    out.0.extend_from_slice(&kept_generations);
+    out.0.extend_from_slice(&link_counts);
+    out.0
+}
+
+/// Decode a docket previously produced by [`encode`], replaying every
+/// generation up to the recorded `used_size` and ignoring any bytes
+/// beyond it.
+pub(crate) fn decode(data: &[u8]) -> IoResult<Snapshot> {
+    let header = read_header(data)?;
+    let valid = &data[..header.used_size];
+    if valid.len() < header.link_counts_offset + header.link_counts_len {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "docket link-counts section truncated"));
+    }
+
+    let nodes = replay_generations(valid, header.link_counts_offset)?;
+    let link_counts: HashMap<Ino, u32> = if header.link_counts_len == 0 {
+        HashMap::new()
+    } else {
+        let start = header.link_counts_offset;
+        bincode::deserialize(&valid[start..start + header.link_counts_len]).map_err(io_err)?
+    };
+
+    Ok(Snapshot {
+        version: crate::snapshot::SNAPSHOT_VERSION,
+        root_ino: ROOT_INO,
+        next_ino: header.next_ino,
+        uid: header.uid,
+        gid: header.gid,
+        link_counts,
+        nodes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot::SNAPSHOT_VERSION;
+
+    // Fixed rather than `SystemTime::now()`, so a node rebuilt with the same
+    // fields compares equal to the one built earlier.
+    fn fixed_time() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+    }
+
+    fn file(name: &str, parent_ino: Ino, content: &[u8]) -> SnapshotNode {
+        let now = fixed_time();
+        SnapshotNode::File(SnapshotFile {
+            name: name.to_string(),
+            content: content.to_vec(),
+            mode: 0o644,
+            uid: 1000,
+            gid: 1000,
+            xattrs: HashMap::new(),
+            parent_ino,
+            atime: now,
+            mtime: now,
+            ctime: now,
+        })
+    }
+
+    fn dir(name: &str, parent_ino: Ino, children: HashMap<String, Ino>) -> SnapshotNode {
+        let now = fixed_time();
+        SnapshotNode::Dir(SnapshotDir {
+            name: name.to_string(),
+            mode: 0o755,
+            uid: 1000,
+            gid: 1000,
+            xattrs: HashMap::new(),
+            parent_ino,
+            children,
+            atime: now,
+            mtime: now,
+            ctime: now,
+        })
+    }
+
+    #[test]
+    fn round_trips_a_full_snapshot() {
+        let mut nodes = HashMap::new();
+        nodes.insert(
+            ROOT_INO,
+            dir("", 0, HashMap::from([("a.txt".to_string(), 2)])),
+        );
+        nodes.insert(2, file("a.txt", ROOT_INO, b"hello"));
+        let mut link_counts = HashMap::new();
+        link_counts.insert(2u64, 1u32);
+
+        let snapshot = Snapshot {
+            version: SNAPSHOT_VERSION,
+            root_ino: ROOT_INO,
+            next_ino: 3,
+            uid: 1000,
+            gid: 1000,
+            link_counts,
+            nodes,
+        };
+
+        let encoded = encode(&snapshot, None);
+        let decoded = decode(&encoded).unwrap();
+        assert_eq!(decoded.next_ino, snapshot.next_ino);
+        assert_eq!(decoded.uid, snapshot.uid);
+        assert_eq!(decoded.gid, snapshot.gid);
+        assert_eq!(decoded.link_counts, snapshot.link_counts);
+        assert_eq!(decoded.nodes, snapshot.nodes);
+    }
+
+    #[test]
+    fn incremental_encode_only_touches_changed_and_removed_nodes() {
+        let mut nodes = HashMap::new();
+        nodes.insert(ROOT_INO, dir("", 0, HashMap::new()));
+        nodes.insert(2, file("unchanged.txt", ROOT_INO, b"same"));
+        nodes.insert(3, file("will_change.txt", ROOT_INO, b"before"));
+        nodes.insert(4, file("will_be_removed.txt", ROOT_INO, b"gone soon"));
+
+        let first = Snapshot {
+            version: SNAPSHOT_VERSION,
+            root_ino: ROOT_INO,
+            next_ino: 5,
+            uid: 0,
+            gid: 0,
+            link_counts: HashMap::new(),
+            nodes,
+        };
+        let first_bytes = encode(&first, None);
+
+        let mut second_nodes = HashMap::new();
+        second_nodes.insert(ROOT_INO, dir("", 0, HashMap::new()));
+        second_nodes.insert(2, file("unchanged.txt", ROOT_INO, b"same"));
+        second_nodes.insert(3, file("will_change.txt", ROOT_INO, b"after"));
+        // ino 4 is removed; nothing inserted for it.
+
+        let second = Snapshot {
+            version: SNAPSHOT_VERSION,
+            root_ino: ROOT_INO,
+            next_ino: 5,
+            uid: 0,
+            gid: 0,
+            link_counts: HashMap::new(),
+            nodes: second_nodes,
+        };
+        let second_bytes = encode(&second, Some(&first_bytes));
+
+        // Only the changed/removed nodes' bytes are appended as a new
+        // generation; the unchanged nodes' prior bytes are carried forward,
+        // so the appended generation is smaller than a from-scratch one
+        // (the file as a whole only grows, since nothing is reclaimed).
+        let from_scratch = encode(&second, None);
+        let appended = second_bytes.len() - first_bytes.len();
+        assert!(
+            appended < from_scratch.len() - HEADER_SIZE,
+            "incremental generation ({}) should be smaller than a full re-encode ({})",
+            appended,
+            from_scratch.len() - HEADER_SIZE
+        );
+        assert!(second_bytes.len() > first_bytes.len());
+
+        let decoded = decode(&second_bytes).unwrap();
+        assert_eq!(decoded.nodes, second.nodes);
+        assert!(!decoded.nodes.contains_key(&4));
+    }
+}
@@ -1,12 +1,19 @@
-use crate::tree::{FileKind, InodeTable, NodeRef, PyDirectory, PyFile, PySymlink};
+use crate::overlay::Overlay;
+use crate::tree::{FileAttr, FileKind, InodeTable, NodeRef, PyDirectory, PyFile, PySymlink};
 use fuser::{
     FileAttr as FuserAttr, FileType, Filesystem, ReplyAttr, ReplyCreate, ReplyData, ReplyDirectory,
-    ReplyEntry, ReplyOpen, ReplyWrite, Request, TimeOrNow,
+    ReplyEntry, ReplyOpen, ReplyWrite, ReplyXattr, Request, TimeOrNow,
+};
+use libc::{
+    EACCES, EBADF, EEXIST, EINVAL, EIO, EISDIR, ENODATA, ENOENT, ENOTDIR, ENOTEMPTY, EPERM, ERANGE,
+    EROFS, R_OK, W_OK, X_OK,
 };
-use libc::{EEXIST, EINVAL, EISDIR, ENOENT, ENOTDIR, ENOTEMPTY};
 use pyo3::prelude::*;
-use pyo3::types::PyBytes;
+use std::collections::HashMap;
 use std::ffi::OsStr;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
@@ -37,30 +44,198 @@ fn to_fuser_attr(attr: &crate::tree::FileAttr) -> FuserAttr {
             FileKind::File => FileType::RegularFile,
             FileKind::Directory => FileType::Directory,
             FileKind::Symlink => FileType::Symlink,
+            FileKind::NamedPipe => FileType::NamedPipe,
+            FileKind::Socket => FileType::Socket,
+            FileKind::CharDevice => FileType::CharDevice,
+            FileKind::BlockDevice => FileType::BlockDevice,
         },
         perm: attr.perm,
         nlink: attr.nlink,
         uid: attr.uid,
         gid: attr.gid,
-        rdev: 0,
+        rdev: attr.rdev,
         blksize: 512,
         flags: 0,
     }
 }
 
+/// Returns whether `uid` belongs to the system group `gid` as a supplementary
+/// group, consulting the process group database (the same source `id(1)` uses).
+fn uid_in_group(uid: u32, gid: u32) -> bool {
+    unsafe {
+        let pwd = libc::getpwuid(uid);
+        if pwd.is_null() {
+            return false;
+        }
+        let base_gid = (*pwd).pw_gid;
+        let mut ngroups: libc::c_int = 32;
+        loop {
+            let mut groups: Vec<libc::gid_t> = vec![0; ngroups as usize];
+            let ret = libc::getgrouplist(
+                (*pwd).pw_name,
+                base_gid,
+                groups.as_mut_ptr(),
+                &mut ngroups,
+            );
+            if ret >= 0 {
+                groups.truncate(ngroups as usize);
+                return groups.iter().any(|&g| g as u32 == gid);
+            }
+            // Buffer was too small; ngroups now holds the required size, retry.
+        }
+    }
+}
+
+/// Check whether a request from `req_uid`/`req_gid` may perform `mask`
+/// (some combination of `R_OK`/`W_OK`/`X_OK`) against a node owned by
+/// `file_uid`/`file_gid` with permission bits `mode`.
+fn check_access(req_uid: u32, req_gid: u32, file_uid: u32, file_gid: u32, mode: u16, mask: i32) -> bool {
+    if req_uid == 0 {
+        // Root is always granted read/write; execute requires some x bit set.
+        if mask & X_OK != 0 {
+            return mode & 0o111 != 0;
+        }
+        return true;
+    }
+
+    let triad = if req_uid == file_uid {
+        (mode >> 6) & 0o7
+    } else if req_gid == file_gid || uid_in_group(req_uid, file_gid) {
+        (mode >> 3) & 0o7
+    } else {
+        mode & 0o7
+    };
+
+    let requested = (mask & (R_OK | W_OK | X_OK)) as u16;
+    (triad & requested) == requested
+}
+
+/// Synthesize a [`FileAttr`] for a lower-layer entry that hasn't been
+/// copied up, from a `stat(2)` of the backing host path. Ownership is
+/// reported as the mounted filesystem's configured `uid`/`gid`, matching
+/// the convention `import_dir` already uses for imported files.
+fn lower_file_attr(ino: u64, path: &Path, metadata: &std::fs::Metadata, uid: u32, gid: u32) -> FileAttr {
+    let kind = if metadata.is_dir() {
+        FileKind::Directory
+    } else if metadata.file_type().is_symlink() {
+        FileKind::Symlink
+    } else {
+        FileKind::File
+    };
+    let size = match kind {
+        FileKind::Directory => 0,
+        FileKind::Symlink => std::fs::read_link(path)
+            .map(|t| t.to_string_lossy().len() as u64)
+            .unwrap_or(0),
+        _ => metadata.len(),
+    };
+    let mtime = metadata.modified().unwrap_or(UNIX_EPOCH);
+    let atime = metadata.accessed().unwrap_or(mtime);
+    FileAttr {
+        ino,
+        size,
+        blocks: size.div_ceil(512),
+        atime,
+        mtime,
+        ctime: mtime,
+        crtime: mtime,
+        kind,
+        perm: (metadata.permissions().mode() & 0o7777) as u16,
+        nlink: if kind == FileKind::Directory { 2 } else { 1 },
+        uid,
+        gid,
+        rdev: 0,
+    }
+}
+
+/// Per-`open(2)` state for a regular file, keyed by the `fh` handed back to
+/// the kernel. Tracks the flags the file was opened with so `read`/`write`
+/// can enforce access-mode (`O_RDONLY`/`O_WRONLY`) and `O_APPEND` semantics
+/// that the bare inode doesn't capture.
+struct OpenFile {
+    ino: u64,
+    flags: i32,
+    /// Cached end-of-file offset for `O_APPEND` handles, refreshed after
+    /// every write through this handle.
+    append_offset: Option<u64>,
+    /// Whether stale suid/sgid bits have already been cleared for this
+    /// handle's writes (cleared at most once, on `O_TRUNC` or first write).
+    suid_sgid_cleared: bool,
+}
+
 /// The FUSE filesystem implementation that wraps the Python-owned tree
 pub struct MemFs {
     pub(crate) inodes: Arc<parking_lot::Mutex<InodeTable>>,
+    /// When set, all mutating operations fail with `EROFS` and `open`/`opendir`
+    /// reject write-intent flags, exposing an immutable view of the tree.
+    pub(crate) read_only: bool,
+    /// Read-only host directory merged underneath the in-memory tree, if any.
+    overlay: Overlay,
+    file_handles: parking_lot::Mutex<HashMap<u64, OpenFile>>,
+    next_fh: AtomicU64,
 }
 
 impl MemFs {
-    pub fn new(inodes: Arc<parking_lot::Mutex<InodeTable>>) -> Self {
-        Self { inodes }
+    pub fn new(
+        inodes: Arc<parking_lot::Mutex<InodeTable>>,
+        read_only: bool,
+        lower_dir: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            inodes,
+            read_only,
+            overlay: Overlay::new(lower_dir),
+            file_handles: parking_lot::Mutex::new(HashMap::new()),
+            next_fh: AtomicU64::new(1),
+        }
+    }
+
+    fn alloc_fh(&self) -> u64 {
+        self.next_fh.fetch_add(1, Ordering::SeqCst)
+    }
+
+    /// Check `mask` (R_OK/W_OK/X_OK) for `req` against the node at `ino`,
+    /// under the already-locked `inodes` table.
+    fn access_allowed(
+        &self,
+        py: Python<'_>,
+        inodes: &InodeTable,
+        req: &Request,
+        ino: u64,
+        mask: i32,
+    ) -> bool {
+        match inodes.getattr(py, ino) {
+            Some(attr) => check_access(req.uid(), req.gid(), attr.uid, attr.gid, attr.perm, mask),
+            None => false,
+        }
+    }
+
+    /// Like [`Self::access_allowed`], but also considers lower-layer entries
+    /// that haven't been copied into the upper tree (and so have no
+    /// `InodeTable` entry of their own).
+    fn access_allowed_any(
+        &self,
+        py: Python<'_>,
+        inodes: &InodeTable,
+        req: &Request,
+        ino: u64,
+        mask: i32,
+    ) -> bool {
+        if let Some(attr) = inodes.getattr(py, ino) {
+            return check_access(req.uid(), req.gid(), attr.uid, attr.gid, attr.perm, mask);
+        }
+        match self.overlay.read_lower(ino).and_then(|p| std::fs::symlink_metadata(&p).ok().map(|m| (p, m))) {
+            Some((path, metadata)) => {
+                let attr = lower_file_attr(ino, &path, &metadata, inodes.uid, inodes.gid);
+                check_access(req.uid(), req.gid(), attr.uid, attr.gid, attr.perm, mask)
+            }
+            None => false,
+        }
     }
 }
 
 impl Filesystem for MemFs {
-    fn lookup(&mut self, _req: &Request, parent: u64, name: &OsStr, reply: ReplyEntry) {
+    fn lookup(&mut self, req: &Request, parent: u64, name: &OsStr, reply: ReplyEntry) {
         let name = match name.to_str() {
             Some(n) => n,
             None => {
@@ -69,11 +244,31 @@ impl Filesystem for MemFs {
             }
         };
 
+        let parent = self.overlay.resolve(parent);
+
         Python::attach(|py| {
             let inodes = self.inodes.lock();
+            if !self.access_allowed_any(py, &inodes, req, parent, X_OK) {
+                reply.error(EACCES);
+                return;
+            }
+            drop(inodes);
+            if InodeTable::ensure_lazy_entry_unlocked(&self.inodes, py, parent, name).is_err() {
+                reply.error(EIO);
+                return;
+            }
+            let mut inodes = self.inodes.lock();
             if let Some(ino) = inodes.lookup(py, parent, name)
                 && let Some(attr) = inodes.getattr(py, ino)
             {
+                inodes.note_lookup(ino);
+                reply.entry(&TTL, &to_fuser_attr(&attr), 0);
+                return;
+            }
+            if !self.overlay.is_whiteout(parent, name)
+                && let Some((ino, path, metadata)) = self.overlay.lookup_lower(parent, name)
+            {
+                let attr = lower_file_attr(ino, &path, &metadata, inodes.uid, inodes.gid);
                 reply.entry(&TTL, &to_fuser_attr(&attr), 0);
                 return;
             }
@@ -81,20 +276,39 @@ impl Filesystem for MemFs {
         });
     }
 
+    fn forget(&mut self, _req: &Request, ino: u64, nlookup: u64) {
+        let ino = self.overlay.resolve(ino);
+        Python::attach(|py| {
+            let mut inodes = self.inodes.lock();
+            inodes.forget(py, ino, nlookup);
+        });
+    }
+
     fn getattr(&mut self, _req: &Request, ino: u64, _fh: Option<u64>, reply: ReplyAttr) {
+        let ino = self.overlay.resolve(ino);
         Python::attach(|py| {
             let inodes = self.inodes.lock();
             if let Some(attr) = inodes.getattr(py, ino) {
                 reply.attr(&TTL, &to_fuser_attr(&attr));
-            } else {
-                reply.error(ENOENT);
+                return;
+            }
+            match self
+                .overlay
+                .read_lower(ino)
+                .and_then(|p| std::fs::symlink_metadata(&p).ok().map(|m| (p, m)))
+            {
+                Some((path, metadata)) => {
+                    let attr = lower_file_attr(ino, &path, &metadata, inodes.uid, inodes.gid);
+                    reply.attr(&TTL, &to_fuser_attr(&attr));
+                }
+                None => reply.error(ENOENT),
             }
         });
     }
 
     fn setattr(
         &mut self,
-        _req: &Request,
+        req: &Request,
         ino: u64,
         mode: Option<u32>,
         _uid: Option<u32>,
@@ -110,9 +324,20 @@ impl Filesystem for MemFs {
         _flags: Option<u32>,
         reply: ReplyAttr,
     ) {
+        if self.read_only && (mode.is_some() || size.is_some() || atime.is_some() || mtime.is_some())
+        {
+            reply.error(EROFS);
+            return;
+        }
+
         Python::attach(|py| {
             let inodes = self.inodes.lock();
 
+            if !self.access_allowed(py, &inodes, req, ino, W_OK) {
+                reply.error(EACCES);
+                return;
+            }
+
             // Handle truncation
             if let Some(new_size) = size
                 && let Some(file_py) = inodes.get_file(ino)
@@ -136,6 +361,9 @@ impl Filesystem for MemFs {
                     Some(NodeRef::Symlink(_)) => {
                         // Symlinks don't have mode - ignore
                     }
+                    Some(NodeRef::Special(s)) => {
+                        s.borrow_mut(py).mode = (new_mode & 0o7777) as u16;
+                    }
                     None => {
                         reply.error(ENOENT);
                         return;
@@ -184,6 +412,16 @@ impl Filesystem for MemFs {
                         }
                         sym.ctime = now;
                     }
+                    Some(NodeRef::Special(s)) => {
+                        let mut special = s.borrow_mut(py);
+                        if let Some(t) = atime {
+                            special.atime = resolve_time(t);
+                        }
+                        if let Some(t) = mtime {
+                            special.mtime = resolve_time(t);
+                        }
+                        special.ctime = now;
+                    }
                     None => {
                         reply.error(ENOENT);
                         return;
@@ -201,20 +439,46 @@ impl Filesystem for MemFs {
 
     fn read(
         &mut self,
-        _req: &Request,
+        req: &Request,
         ino: u64,
-        _fh: u64,
+        fh: u64,
         offset: i64,
         size: u32,
         _flags: i32,
         _lock_owner: Option<u64>,
         reply: ReplyData,
     ) {
+        if offset < 0 {
+            reply.error(EINVAL);
+            return;
+        }
+
+        {
+            let handles = self.file_handles.lock();
+            match handles.get(&fh) {
+                Some(h) if h.ino != ino || h.flags & libc::O_ACCMODE == libc::O_WRONLY => {
+                    reply.error(EBADF);
+                    return;
+                }
+                Some(_) => {}
+                None => {
+                    reply.error(EBADF);
+                    return;
+                }
+            }
+        }
+
+        let ino = self.overlay.resolve(ino);
+
         Python::attach(|py| {
             let inodes = self.inodes.lock();
+            if !self.access_allowed_any(py, &inodes, req, ino, R_OK) {
+                reply.error(EACCES);
+                return;
+            }
             if let Some(file_py) = inodes.get_file(ino) {
                 let file = file_py.borrow(py);
-                let content = file.content.bind(py).as_bytes();
+                let content = file.assemble(py);
                 let start = offset as usize;
                 if start >= content.len() {
                     reply.data(&[]);
@@ -222,17 +486,28 @@ impl Filesystem for MemFs {
                     let end = (start + size as usize).min(content.len());
                     reply.data(&content[start..end]);
                 }
-            } else {
-                reply.error(ENOENT);
+                return;
+            }
+            match self.overlay.read_lower(ino).and_then(|p| std::fs::read(p).ok()) {
+                Some(content) => {
+                    let start = offset as usize;
+                    if start >= content.len() {
+                        reply.data(&[]);
+                    } else {
+                        let end = (start + size as usize).min(content.len());
+                        reply.data(&content[start..end]);
+                    }
+                }
+                None => reply.error(ENOENT),
             }
         });
     }
 
     fn write(
         &mut self,
-        _req: &Request,
+        req: &Request,
         ino: u64,
-        _fh: u64,
+        fh: u64,
         offset: i64,
         data: &[u8],
         _write_flags: u32,
@@ -240,12 +515,56 @@ impl Filesystem for MemFs {
         _lock_owner: Option<u64>,
         reply: ReplyWrite,
     ) {
+        if self.read_only {
+            reply.error(EROFS);
+            return;
+        }
+
+        if offset < 0 {
+            reply.error(EINVAL);
+            return;
+        }
+
+        let append = {
+            let handles = self.file_handles.lock();
+            match handles.get(&fh) {
+                Some(h) if h.ino != ino || h.flags & libc::O_ACCMODE == libc::O_RDONLY => {
+                    reply.error(EBADF);
+                    return;
+                }
+                Some(h) => h.append_offset.is_some(),
+                None => {
+                    reply.error(EBADF);
+                    return;
+                }
+            }
+        };
+
+        let ino = self.overlay.resolve(ino);
+
         Python::attach(|py| {
-            let inodes = self.inodes.lock();
+            let mut inodes = self.inodes.lock();
+            if !self.access_allowed_any(py, &inodes, req, ino, W_OK) {
+                reply.error(EACCES);
+                return;
+            }
+
+            let ino = if inodes.get_file(ino).is_some() {
+                ino
+            } else {
+                match self.overlay.copy_up_file(py, &mut inodes, ino) {
+                    Ok(upper_ino) => upper_ino,
+                    Err(_) => {
+                        reply.error(EIO);
+                        return;
+                    }
+                }
+            };
+
             if let Some(file_py) = inodes.get_file(ino) {
                 let mut file = file_py.borrow_mut(py);
-                let current = file.content.bind(py).as_bytes().to_vec();
-                let offset = offset as usize;
+                let current = file.assemble(py);
+                let offset = if append { current.len() } else { offset as usize };
 
                 // Extend if necessary
                 let needed_size = offset + data.len();
@@ -259,9 +578,19 @@ impl Filesystem for MemFs {
 
                 // Write the data
                 new_content[offset..offset + data.len()].copy_from_slice(data);
-                file.content = PyBytes::new(py, &new_content).into();
-                file.mtime = SystemTime::now();
-                file.ctime = SystemTime::now();
+                let new_len = new_content.len() as u64;
+                file.replace_content(py, &new_content);
+
+                let mut handles = self.file_handles.lock();
+                if let Some(h) = handles.get_mut(&fh) {
+                    if h.append_offset.is_some() {
+                        h.append_offset = Some(new_len);
+                    }
+                    if !h.suid_sgid_cleared {
+                        file.mode &= !((libc::S_ISUID | libc::S_ISGID) as u16);
+                        h.suid_sgid_cleared = true;
+                    }
+                }
 
                 reply.written(data.len() as u32);
             } else {
@@ -272,15 +601,29 @@ impl Filesystem for MemFs {
 
     fn readdir(
         &mut self,
-        _req: &Request,
+        req: &Request,
         ino: u64,
         _fh: u64,
         offset: i64,
         mut reply: ReplyDirectory,
     ) {
+        let ino = self.overlay.resolve(ino);
+
         Python::attach(|py| {
             let inodes = self.inodes.lock();
 
+            if !self.access_allowed_any(py, &inodes, req, ino, R_OK) {
+                reply.error(EACCES);
+                return;
+            }
+            drop(inodes);
+
+            if InodeTable::ensure_lazy_listed_unlocked(&self.inodes, py, ino).is_err() {
+                reply.error(EIO);
+                return;
+            }
+
+            let inodes = self.inodes.lock();
             if let Some(dir_py) = inodes.get_dir(ino) {
                 let dir = dir_py.borrow(py);
                 let mut entries: Vec<(u64, FileType, String)> = vec![
@@ -293,11 +636,37 @@ impl Filesystem for MemFs {
                         Some(NodeRef::File(_)) => FileType::RegularFile,
                         Some(NodeRef::Dir(_)) => FileType::Directory,
                         Some(NodeRef::Symlink(_)) => FileType::Symlink,
+                        Some(NodeRef::Special(s)) => match s.borrow(py).kind {
+                            FileKind::NamedPipe => FileType::NamedPipe,
+                            FileKind::Socket => FileType::Socket,
+                            FileKind::CharDevice => FileType::CharDevice,
+                            FileKind::BlockDevice => FileType::BlockDevice,
+                            FileKind::File | FileKind::Directory | FileKind::Symlink => continue,
+                        },
                         None => continue,
                     };
                     entries.push((child_ino, kind, name.clone()));
                 }
 
+                // Merge in any lower-only siblings not yet copied up and not
+                // shadowed by an upper entry or a whiteout.
+                for name in self.overlay.list_lower(ino) {
+                    if dir.children.contains_key(&name) || self.overlay.is_whiteout(ino, &name) {
+                        continue;
+                    }
+                    if let Some((child_ino, _path, metadata)) = self.overlay.lookup_lower(ino, &name)
+                    {
+                        let kind = if metadata.is_dir() {
+                            FileType::Directory
+                        } else if metadata.file_type().is_symlink() {
+                            FileType::Symlink
+                        } else {
+                            FileType::RegularFile
+                        };
+                        entries.push((child_ino, kind, name));
+                    }
+                }
+
                 for (i, (child_ino, kind, name)) in entries.iter().enumerate().skip(offset as usize)
                 {
                     if reply.add(*child_ino, (i + 1) as i64, *kind, name) {
@@ -305,22 +674,61 @@ impl Filesystem for MemFs {
                     }
                 }
                 reply.ok();
-            } else {
-                reply.error(ENOTDIR);
+                return;
+            }
+
+            // Purely lower-only directory: list straight from the host.
+            // (".." is omitted: a lower-only directory's parent inode isn't
+            // tracked, since it's only ever reached by descending from one
+            // that is.)
+            if self.overlay.read_lower(ino).is_some() {
+                let mut entries: Vec<(u64, FileType, String)> =
+                    vec![(ino, FileType::Directory, ".".to_string())];
+                for name in self.overlay.list_lower(ino) {
+                    if self.overlay.is_whiteout(ino, &name) {
+                        continue;
+                    }
+                    if let Some((child_ino, _path, metadata)) = self.overlay.lookup_lower(ino, &name)
+                    {
+                        let kind = if metadata.is_dir() {
+                            FileType::Directory
+                        } else if metadata.file_type().is_symlink() {
+                            FileType::Symlink
+                        } else {
+                            FileType::RegularFile
+                        };
+                        entries.push((child_ino, kind, name));
+                    }
+                }
+                for (i, (child_ino, kind, name)) in entries.iter().enumerate().skip(offset as usize)
+                {
+                    if reply.add(*child_ino, (i + 1) as i64, *kind, name) {
+                        break;
+                    }
+                }
+                reply.ok();
+                return;
             }
+
+            reply.error(ENOTDIR);
         });
     }
 
     fn create(
         &mut self,
-        _req: &Request,
+        req: &Request,
         parent: u64,
         name: &OsStr,
         mode: u32,
         _umask: u32,
-        _flags: i32,
+        flags: i32,
         reply: ReplyCreate,
     ) {
+        if self.read_only {
+            reply.error(EROFS);
+            return;
+        }
+
         let name = match name.to_str() {
             Some(n) => n,
             None => {
@@ -332,6 +740,11 @@ impl Filesystem for MemFs {
         Python::attach(|py| {
             let mut inodes = self.inodes.lock();
 
+            if !self.access_allowed(py, &inodes, req, parent, W_OK | X_OK) {
+                reply.error(EACCES);
+                return;
+            }
+
             // Check if name already exists
             if inodes.lookup(py, parent, name).is_some() {
                 reply.error(EEXIST);
@@ -344,7 +757,23 @@ impl Filesystem for MemFs {
                     Ok(file_py) => match inodes.insert_file(py, parent, file_py) {
                         Ok(ino) => {
                             if let Some(attr) = inodes.getattr(py, ino) {
-                                reply.created(&TTL, &to_fuser_attr(&attr), 0, 0, 0);
+                                inodes.note_lookup(ino);
+                                let append_offset = if flags & libc::O_APPEND != 0 {
+                                    Some(0)
+                                } else {
+                                    None
+                                };
+                                let fh = self.alloc_fh();
+                                self.file_handles.lock().insert(
+                                    fh,
+                                    OpenFile {
+                                        ino,
+                                        flags,
+                                        append_offset,
+                                        suid_sgid_cleared: false,
+                                    },
+                                );
+                                reply.created(&TTL, &to_fuser_attr(&attr), 0, fh, 0);
                             } else {
                                 reply.error(ENOENT);
                             }
@@ -360,13 +789,18 @@ impl Filesystem for MemFs {
 
     fn mkdir(
         &mut self,
-        _req: &Request,
+        req: &Request,
         parent: u64,
         name: &OsStr,
         mode: u32,
         _umask: u32,
         reply: ReplyEntry,
     ) {
+        if self.read_only {
+            reply.error(EROFS);
+            return;
+        }
+
         let name = match name.to_str() {
             Some(n) => n,
             None => {
@@ -378,6 +812,11 @@ impl Filesystem for MemFs {
         Python::attach(|py| {
             let mut inodes = self.inodes.lock();
 
+            if !self.access_allowed(py, &inodes, req, parent, W_OK | X_OK) {
+                reply.error(EACCES);
+                return;
+            }
+
             // Check if name already exists
             if inodes.lookup(py, parent, name).is_some() {
                 reply.error(EEXIST);
@@ -390,6 +829,7 @@ impl Filesystem for MemFs {
                 Ok(dir_py) => match inodes.insert_dir(py, parent, dir_py) {
                     Ok(ino) => {
                         if let Some(attr) = inodes.getattr(py, ino) {
+                            inodes.note_lookup(ino);
                             reply.entry(&TTL, &to_fuser_attr(&attr), 0);
                         } else {
                             reply.error(ENOENT);
@@ -402,7 +842,21 @@ impl Filesystem for MemFs {
         });
     }
 
-    fn unlink(&mut self, _req: &Request, parent: u64, name: &OsStr, reply: fuser::ReplyEmpty) {
+    fn mknod(
+        &mut self,
+        req: &Request,
+        parent: u64,
+        name: &OsStr,
+        mode: u32,
+        _umask: u32,
+        rdev: u32,
+        reply: ReplyEntry,
+    ) {
+        if self.read_only {
+            reply.error(EROFS);
+            return;
+        }
+
         let name = match name.to_str() {
             Some(n) => n,
             None => {
@@ -411,9 +865,72 @@ impl Filesystem for MemFs {
             }
         };
 
+        let kind = match mode & libc::S_IFMT {
+            libc::S_IFIFO => FileKind::NamedPipe,
+            libc::S_IFSOCK => FileKind::Socket,
+            libc::S_IFCHR => FileKind::CharDevice,
+            libc::S_IFBLK => FileKind::BlockDevice,
+            _ => {
+                reply.error(EINVAL);
+                return;
+            }
+        };
+
         Python::attach(|py| {
             let mut inodes = self.inodes.lock();
 
+            if !self.access_allowed(py, &inodes, req, parent, W_OK | X_OK) {
+                reply.error(EACCES);
+                return;
+            }
+
+            if inodes.lookup(py, parent, name).is_some() {
+                reply.error(EEXIST);
+                return;
+            }
+
+            let special = crate::tree::PySpecial::new(name.to_string(), kind, (mode & 0o7777) as u16, rdev);
+            match Py::new(py, special) {
+                Ok(special_py) => match inodes.insert_special(py, parent, special_py) {
+                    Ok(ino) => {
+                        if let Some(attr) = inodes.getattr(py, ino) {
+                            inodes.note_lookup(ino);
+                            reply.entry(&TTL, &to_fuser_attr(&attr), 0);
+                        } else {
+                            reply.error(ENOENT);
+                        }
+                    }
+                    Err(_) => reply.error(EINVAL),
+                },
+                Err(_) => reply.error(EINVAL),
+            }
+        });
+    }
+
+    fn unlink(&mut self, req: &Request, parent: u64, name: &OsStr, reply: fuser::ReplyEmpty) {
+        if self.read_only {
+            reply.error(EROFS);
+            return;
+        }
+
+        let name = match name.to_str() {
+            Some(n) => n,
+            None => {
+                reply.error(EINVAL);
+                return;
+            }
+        };
+
+        let parent = self.overlay.resolve(parent);
+
+        Python::attach(|py| {
+            let mut inodes = self.inodes.lock();
+
+            if !self.access_allowed_any(py, &inodes, req, parent, W_OK | X_OK) {
+                reply.error(EACCES);
+                return;
+            }
+
             if let Some(ino) = inodes.lookup(py, parent, name) {
                 // Make sure it's a file, not a directory
                 if let Some(NodeRef::Dir(_)) = inodes.get(ino) {
@@ -421,17 +938,40 @@ impl Filesystem for MemFs {
                     return;
                 }
 
-                match inodes.remove(py, ino) {
-                    Ok(Some(_)) => reply.ok(),
+                match inodes.remove(py, parent, name) {
+                    Ok(Some(_)) => {
+                        if self.overlay.lower_child_exists(parent, name) {
+                            self.overlay.add_whiteout(parent, name);
+                        }
+                        reply.ok();
+                    }
                     _ => reply.error(ENOENT),
                 }
-            } else {
+                return;
+            }
+
+            if self.overlay.is_whiteout(parent, name) {
                 reply.error(ENOENT);
+                return;
+            }
+
+            match self.overlay.lookup_lower(parent, name) {
+                Some((_, _, metadata)) if metadata.is_dir() => reply.error(EISDIR),
+                Some(_) => {
+                    self.overlay.add_whiteout(parent, name);
+                    reply.ok();
+                }
+                None => reply.error(ENOENT),
             }
         });
     }
 
-    fn rmdir(&mut self, _req: &Request, parent: u64, name: &OsStr, reply: fuser::ReplyEmpty) {
+    fn rmdir(&mut self, req: &Request, parent: u64, name: &OsStr, reply: fuser::ReplyEmpty) {
+        if self.read_only {
+            reply.error(EROFS);
+            return;
+        }
+
         let name = match name.to_str() {
             Some(n) => n,
             None => {
@@ -440,9 +980,16 @@ impl Filesystem for MemFs {
             }
         };
 
+        let parent = self.overlay.resolve(parent);
+
         Python::attach(|py| {
             let mut inodes = self.inodes.lock();
 
+            if !self.access_allowed_any(py, &inodes, req, parent, W_OK | X_OK) {
+                reply.error(EACCES);
+                return;
+            }
+
             if let Some(ino) = inodes.lookup(py, parent, name) {
                 // Make sure it's a directory
                 match inodes.get(ino) {
@@ -452,7 +999,7 @@ impl Filesystem for MemFs {
                             return;
                         }
                     }
-                    Some(NodeRef::File(_)) | Some(NodeRef::Symlink(_)) => {
+                    Some(NodeRef::File(_)) | Some(NodeRef::Symlink(_)) | Some(NodeRef::Special(_)) => {
                         reply.error(ENOTDIR);
                         return;
                     }
@@ -462,24 +1009,124 @@ impl Filesystem for MemFs {
                     }
                 }
 
-                match inodes.remove(py, ino) {
-                    Ok(Some(_)) => reply.ok(),
+                match inodes.remove(py, parent, name) {
+                    Ok(Some(_)) => {
+                        if self.overlay.lower_child_exists(parent, name) {
+                            self.overlay.add_whiteout(parent, name);
+                        }
+                        reply.ok();
+                    }
                     _ => reply.error(ENOENT),
                 }
-            } else {
+                return;
+            }
+
+            if self.overlay.is_whiteout(parent, name) {
                 reply.error(ENOENT);
+                return;
+            }
+
+            match self.overlay.lookup_lower(parent, name) {
+                Some((child_ino, _, metadata)) if metadata.is_dir() => {
+                    if self.overlay.list_lower(child_ino).is_empty() {
+                        self.overlay.add_whiteout(parent, name);
+                        reply.ok();
+                    } else {
+                        reply.error(ENOTEMPTY);
+                    }
+                }
+                Some(_) => reply.error(ENOTDIR),
+                None => reply.error(ENOENT),
             }
         });
     }
 
-    fn open(&mut self, _req: &Request, ino: u64, _flags: i32, reply: ReplyOpen) {
-        Python::attach(|_py| {
-            let inodes = self.inodes.lock();
-            if inodes.get_file(ino).is_some() {
-                reply.opened(0, 0);
-            } else {
+    fn open(&mut self, req: &Request, ino: u64, flags: i32, reply: ReplyOpen) {
+        if self.read_only
+            && flags & (libc::O_WRONLY | libc::O_RDWR | libc::O_TRUNC) != 0
+        {
+            reply.error(EROFS);
+            return;
+        }
+
+        let orig_ino = ino;
+
+        Python::attach(|py| {
+            let mut inodes = self.inodes.lock();
+            let resolved = self.overlay.resolve(orig_ino);
+            let is_lower_only =
+                inodes.get_file(resolved).is_none() && self.overlay.lower_path_for(resolved).is_some();
+
+            if !is_lower_only && inodes.get_file(resolved).is_none() {
                 reply.error(ENOENT);
+                return;
             }
+
+            let acc_mode = flags & libc::O_ACCMODE;
+            let mut mask = 0;
+            if acc_mode != libc::O_WRONLY {
+                mask |= R_OK;
+            }
+            if acc_mode != libc::O_RDONLY {
+                mask |= W_OK;
+            }
+            if !self.access_allowed_any(py, &inodes, req, resolved, mask) {
+                reply.error(EACCES);
+                return;
+            }
+
+            // A truncating open can't be deferred like a write: copy the
+            // lower file up right away so the truncation lands in memory.
+            let ino = if is_lower_only && flags & libc::O_TRUNC != 0 {
+                match self.overlay.copy_up_file(py, &mut inodes, resolved) {
+                    Ok(upper_ino) => upper_ino,
+                    Err(_) => {
+                        reply.error(EIO);
+                        return;
+                    }
+                }
+            } else {
+                resolved
+            };
+
+            if flags & libc::O_TRUNC != 0
+                && let Some(file_py) = inodes.get_file(ino)
+            {
+                let mut file = file_py.borrow_mut(py);
+                if file.truncate(py, 0).is_err() {
+                    reply.error(EINVAL);
+                    return;
+                }
+                file.mode &= !((libc::S_ISUID | libc::S_ISGID) as u16);
+            }
+
+            let append_offset = if flags & libc::O_APPEND != 0 {
+                match inodes.get_file(ino) {
+                    Some(f) => Some(f.borrow(py).len(py) as u64),
+                    // Not yet copied up: the size lives on the lower file
+                    // until the first write triggers copy-up.
+                    None => self
+                        .overlay
+                        .read_lower(ino)
+                        .and_then(|p| std::fs::metadata(p).ok())
+                        .map(|m| m.len()),
+                }
+            } else {
+                None
+            };
+
+            let fh = self.alloc_fh();
+            self.file_handles.lock().insert(
+                fh,
+                OpenFile {
+                    ino: orig_ino,
+                    flags,
+                    append_offset,
+                    suid_sgid_cleared: flags & libc::O_TRUNC != 0,
+                },
+            );
+
+            reply.opened(fh, 0);
         });
     }
 
@@ -487,23 +1134,37 @@ impl Filesystem for MemFs {
         &mut self,
         _req: &Request,
         _ino: u64,
-        _fh: u64,
+        fh: u64,
         _flags: i32,
         _lock_owner: Option<u64>,
         _flush: bool,
         reply: fuser::ReplyEmpty,
     ) {
+        self.file_handles.lock().remove(&fh);
         reply.ok();
     }
 
-    fn opendir(&mut self, _req: &Request, ino: u64, _flags: i32, reply: ReplyOpen) {
-        Python::attach(|_py| {
+    fn opendir(&mut self, req: &Request, ino: u64, flags: i32, reply: ReplyOpen) {
+        if self.read_only
+            && flags & (libc::O_WRONLY | libc::O_RDWR | libc::O_TRUNC) != 0
+        {
+            reply.error(EROFS);
+            return;
+        }
+
+        let ino = self.overlay.resolve(ino);
+
+        Python::attach(|py| {
             let inodes = self.inodes.lock();
-            if inodes.get_dir(ino).is_some() {
-                reply.opened(0, 0);
-            } else {
+            if inodes.get_dir(ino).is_none() && self.overlay.read_lower(ino).is_none() {
                 reply.error(ENOENT);
+                return;
             }
+            if !self.access_allowed_any(py, &inodes, req, ino, R_OK) {
+                reply.error(EACCES);
+                return;
+            }
+            reply.opened(0, 0);
         });
     }
 
@@ -520,14 +1181,19 @@ impl Filesystem for MemFs {
 
     fn rename(
         &mut self,
-        _req: &Request,
+        req: &Request,
         parent: u64,
         name: &OsStr,
         newparent: u64,
         newname: &OsStr,
-        _flags: u32,
+        flags: u32,
         reply: fuser::ReplyEmpty,
     ) {
+        if self.read_only {
+            reply.error(EROFS);
+            return;
+        }
+
         let name = match name.to_str() {
             Some(n) => n,
             None => {
@@ -543,9 +1209,23 @@ impl Filesystem for MemFs {
             }
         };
 
+        let noreplace = flags & libc::RENAME_NOREPLACE as u32 != 0;
+        let exchange = flags & libc::RENAME_EXCHANGE as u32 != 0;
+        if noreplace && exchange {
+            reply.error(EINVAL);
+            return;
+        }
+
         Python::attach(|py| {
             let mut inodes = self.inodes.lock();
 
+            if !self.access_allowed(py, &inodes, req, parent, W_OK | X_OK)
+                || !self.access_allowed(py, &inodes, req, newparent, W_OK | X_OK)
+            {
+                reply.error(EACCES);
+                return;
+            }
+
             // Find the source inode
             let ino = match inodes.lookup(py, parent, name) {
                 Some(ino) => ino,
@@ -555,8 +1235,31 @@ impl Filesystem for MemFs {
                 }
             };
 
+            let existing_ino = inodes.lookup(py, newparent, newname);
+
+            if exchange {
+                if existing_ino.is_none() {
+                    reply.error(ENOENT);
+                    return;
+                }
+                if inodes
+                    .exchange(py, parent, name, newparent, newname)
+                    .is_err()
+                {
+                    reply.error(EINVAL);
+                    return;
+                }
+                reply.ok();
+                return;
+            }
+
             // Check if destination exists - if so, remove it first
-            if let Some(existing_ino) = inodes.lookup(py, newparent, newname) {
+            if let Some(existing_ino) = existing_ino {
+                if noreplace {
+                    reply.error(EEXIST);
+                    return;
+                }
+
                 // Can't overwrite directory with file or vice versa
                 let src_is_dir = matches!(inodes.get(ino), Some(NodeRef::Dir(_)));
                 let dst_is_dir = matches!(inodes.get(existing_ino), Some(NodeRef::Dir(_)));
@@ -576,7 +1279,7 @@ impl Filesystem for MemFs {
                 }
 
                 // Remove the destination
-                if inodes.remove(py, existing_ino).is_err() {
+                if inodes.remove(py, newparent, newname).is_err() {
                     reply.error(EINVAL);
                     return;
                 }
@@ -594,12 +1297,17 @@ impl Filesystem for MemFs {
 
     fn symlink(
         &mut self,
-        _req: &Request,
+        req: &Request,
         parent: u64,
         name: &OsStr,
         link: &std::path::Path,
         reply: ReplyEntry,
     ) {
+        if self.read_only {
+            reply.error(EROFS);
+            return;
+        }
+
         let name = match name.to_str() {
             Some(n) => n,
             None => {
@@ -618,6 +1326,11 @@ impl Filesystem for MemFs {
         Python::attach(|py| {
             let mut inodes = self.inodes.lock();
 
+            if !self.access_allowed(py, &inodes, req, parent, W_OK | X_OK) {
+                reply.error(EACCES);
+                return;
+            }
+
             // Check if name already exists
             if inodes.lookup(py, parent, name).is_some() {
                 reply.error(EEXIST);
@@ -630,6 +1343,7 @@ impl Filesystem for MemFs {
                 Ok(symlink_py) => match inodes.insert_symlink(py, parent, symlink_py) {
                     Ok(ino) => {
                         if let Some(attr) = inodes.getattr(py, ino) {
+                            inodes.note_lookup(ino);
                             reply.entry(&TTL, &to_fuser_attr(&attr), 0);
                         } else {
                             reply.error(ENOENT);
@@ -642,6 +1356,59 @@ impl Filesystem for MemFs {
         });
     }
 
+    fn link(
+        &mut self,
+        req: &Request,
+        ino: u64,
+        newparent: u64,
+        newname: &OsStr,
+        reply: ReplyEntry,
+    ) {
+        if self.read_only {
+            reply.error(EROFS);
+            return;
+        }
+
+        let newname = match newname.to_str() {
+            Some(n) => n,
+            None => {
+                reply.error(EINVAL);
+                return;
+            }
+        };
+
+        Python::attach(|py| {
+            let mut inodes = self.inodes.lock();
+
+            if !self.access_allowed(py, &inodes, req, newparent, W_OK | X_OK) {
+                reply.error(EACCES);
+                return;
+            }
+
+            if matches!(inodes.get(ino), Some(NodeRef::Dir(_))) {
+                reply.error(EPERM);
+                return;
+            }
+
+            if inodes.lookup(py, newparent, newname).is_some() {
+                reply.error(EEXIST);
+                return;
+            }
+
+            match inodes.link(py, ino, newparent, newname) {
+                Ok(()) => {
+                    if let Some(attr) = inodes.getattr(py, ino) {
+                        inodes.note_lookup(ino);
+                        reply.entry(&TTL, &to_fuser_attr(&attr), 0);
+                    } else {
+                        reply.error(ENOENT);
+                    }
+                }
+                Err(_) => reply.error(EINVAL),
+            }
+        });
+    }
+
     fn readlink(&mut self, _req: &Request, ino: u64, reply: fuser::ReplyData) {
         Python::attach(|py| {
             let inodes = self.inodes.lock();
@@ -692,14 +1459,17 @@ impl Filesystem for MemFs {
         reply.ok();
     }
 
-    fn access(&mut self, _req: &Request, ino: u64, _mask: i32, reply: fuser::ReplyEmpty) {
-        // Simple access check - just verify the inode exists
+    fn access(&mut self, req: &Request, ino: u64, mask: i32, reply: fuser::ReplyEmpty) {
         Python::attach(|py| {
             let inodes = self.inodes.lock();
-            if inodes.getattr(py, ino).is_some() {
+            if inodes.getattr(py, ino).is_none() {
+                reply.error(ENOENT);
+                return;
+            }
+            if self.access_allowed(py, &inodes, req, ino, mask) {
                 reply.ok();
             } else {
-                reply.error(ENOENT);
+                reply.error(EACCES);
             }
         });
     }
@@ -715,4 +1485,153 @@ impl Filesystem for MemFs {
         // In-memory filesystem - nothing to flush
         reply.ok();
     }
+
+    fn setxattr(
+        &mut self,
+        _req: &Request,
+        ino: u64,
+        name: &OsStr,
+        value: &[u8],
+        _flags: i32,
+        _position: u32,
+        reply: fuser::ReplyEmpty,
+    ) {
+        if self.read_only {
+            reply.error(EROFS);
+            return;
+        }
+
+        let name = match name.to_str() {
+            Some(n) => n,
+            None => {
+                reply.error(EINVAL);
+                return;
+            }
+        };
+
+        Python::attach(|py| {
+            let inodes = self.inodes.lock();
+            if inodes.setxattr(py, ino, name, value) {
+                reply.ok();
+            } else {
+                reply.error(ENOENT);
+            }
+        });
+    }
+
+    fn getxattr(&mut self, _req: &Request, ino: u64, name: &OsStr, size: u32, reply: ReplyXattr) {
+        let name = match name.to_str() {
+            Some(n) => n,
+            None => {
+                reply.error(EINVAL);
+                return;
+            }
+        };
+
+        Python::attach(|py| {
+            let inodes = self.inodes.lock();
+            if inodes.getattr(py, ino).is_none() {
+                reply.error(ENOENT);
+                return;
+            }
+            match inodes.getxattr(py, ino, name) {
+                Some(value) => {
+                    if size == 0 {
+                        reply.size(value.len() as u32);
+                    } else if value.len() > size as usize {
+                        reply.error(ERANGE);
+                    } else {
+                        reply.data(&value);
+                    }
+                }
+                None => reply.error(ENODATA),
+            }
+        });
+    }
+
+    fn listxattr(&mut self, _req: &Request, ino: u64, size: u32, reply: ReplyXattr) {
+        Python::attach(|py| {
+            let inodes = self.inodes.lock();
+            match inodes.listxattr(py, ino) {
+                Some(names) => {
+                    if size == 0 {
+                        reply.size(names.len() as u32);
+                    } else if names.len() > size as usize {
+                        reply.error(ERANGE);
+                    } else {
+                        reply.data(&names);
+                    }
+                }
+                None => reply.error(ENOENT),
+            }
+        });
+    }
+
+    fn removexattr(&mut self, _req: &Request, ino: u64, name: &OsStr, reply: fuser::ReplyEmpty) {
+        if self.read_only {
+            reply.error(EROFS);
+            return;
+        }
+
+        let name = match name.to_str() {
+            Some(n) => n,
+            None => {
+                reply.error(EINVAL);
+                return;
+            }
+        };
+
+        Python::attach(|py| {
+            let inodes = self.inodes.lock();
+            match inodes.removexattr(py, ino, name) {
+                Some(true) => reply.ok(),
+                Some(false) => reply.error(ENODATA),
+                None => reply.error(ENOENT),
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_access;
+    use libc::{R_OK, W_OK, X_OK};
+
+    const MODE: u16 = 0o740; // owner rwx, group r--, other ---
+
+    #[test]
+    fn owner_triad_governs_access_for_the_owning_uid() {
+        // Owner bits on MODE are rwx; a mismatched gid doesn't matter since
+        // the uid already matches the file's owner.
+        assert!(check_access(100, 999, 100, 200, MODE, R_OK | W_OK | X_OK));
+        // With only read granted to the owner, write/execute are denied.
+        assert!(!check_access(100, 999, 100, 200, 0o400, W_OK | X_OK));
+    }
+
+    #[test]
+    fn group_triad_governs_access_for_a_matching_gid() {
+        // Group bits on MODE are r--, so a non-owner in the matching group
+        // gets read but not write or execute.
+        assert!(check_access(101, 200, 100, 200, MODE, R_OK));
+        assert!(!check_access(101, 200, 100, 200, MODE, W_OK));
+        assert!(!check_access(101, 200, 100, 200, MODE, X_OK));
+    }
+
+    #[test]
+    fn other_triad_governs_access_for_an_unrelated_requester() {
+        // Other bits on MODE are ---, and an implausibly large uid/gid pair
+        // won't match the owner, the group, or any supplementary group.
+        let uid = 4_000_000_001;
+        let gid = 4_000_000_002;
+        assert!(!check_access(uid, gid, 100, 200, MODE, R_OK));
+        assert!(!check_access(uid, gid, 100, 200, MODE, W_OK));
+        assert!(!check_access(uid, gid, 100, 200, MODE, X_OK));
+    }
+
+    #[test]
+    fn root_bypasses_permission_bits_except_requiring_some_execute_bit() {
+        assert!(check_access(0, 0, 100, 200, 0o600, R_OK | W_OK));
+        assert!(!check_access(0, 0, 100, 200, 0o600, X_OK));
+        assert!(check_access(0, 0, 100, 200, 0o700, X_OK));
+    }
 }
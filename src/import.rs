@@ -0,0 +1,175 @@
+//! Bulk-import of a real host directory into an [`InodeTable`], filtered by
+//! gitignore/deno-publish-style include/exclude glob patterns.
+use crate::tree::{Ino, InodeTable, PyDirectory, PyFile, PySymlink};
+use pyo3::exceptions::PyOSError;
+use pyo3::prelude::*;
+use std::collections::HashMap;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+/// Match a single glob pattern (`*`, `**`, `?`) against a `/`-separated
+/// relative path. `**` matches any number of path segments (including none);
+/// `*`/`?` only match within a single segment.
+pub(crate) fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('/').collect();
+    let segments: Vec<&str> = path.split('/').collect();
+    match_segments(&pattern, &segments)
+}
+
+fn match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match (pattern.first(), path.first()) {
+        (None, None) => true,
+        (None, Some(_)) => false,
+        (Some(&"**"), _) => {
+            (0..=path.len()).any(|n| match_segments(&pattern[1..], &path[n..]))
+        }
+        (Some(p), Some(s)) => match_segment(p, s) && match_segments(&pattern[1..], &path[1..]),
+        (Some(_), None) => false,
+    }
+}
+
+fn match_segment(pattern: &str, segment: &str) -> bool {
+    fn helper(p: &[u8], s: &[u8]) -> bool {
+        match (p.first(), s.first()) {
+            (None, None) => true,
+            (Some(b'*'), _) => (0..=s.len()).any(|i| helper(&p[1..], &s[i..])),
+            (Some(b'?'), Some(_)) => helper(&p[1..], &s[1..]),
+            (Some(pc), Some(sc)) if pc == sc => helper(&p[1..], &s[1..]),
+            _ => false,
+        }
+    }
+    helper(pattern.as_bytes(), segment.as_bytes())
+}
+
+/// Whether `path` (relative to the import root, `/`-separated) should be
+/// imported: it must match `include` (if any patterns were given), and must
+/// not be excluded — where a later `!`-prefixed pattern in `exclude` can
+/// re-include something a preceding, coarser pattern excluded.
+fn is_included(path: &str, include: &[String], exclude: &[String]) -> bool {
+    if !include.is_empty() && !include.iter().any(|p| glob_match(p, path)) {
+        return false;
+    }
+
+    let mut excluded = false;
+    for pattern in exclude {
+        match pattern.strip_prefix('!') {
+            Some(negated) => {
+                if glob_match(negated, path) {
+                    excluded = false;
+                }
+            }
+            None => {
+                if glob_match(pattern, path) {
+                    excluded = true;
+                }
+            }
+        }
+    }
+    !excluded
+}
+
+/// Recursively list every entry under `host_path` as paths relative to it.
+fn collect_entries(host_path: &Path, rel: &Path, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(host_path.join(rel))? {
+        let entry = entry?;
+        let rel_child = rel.join(entry.file_name());
+        let is_dir = entry.file_type()?.is_dir();
+        out.push(rel_child.clone());
+        if is_dir {
+            collect_entries(host_path, &rel_child, out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Get or create the directory at `rel_path` (relative to `host_path`),
+/// creating any missing ancestors along the way.
+fn ensure_dir(
+    py: Python<'_>,
+    inodes: &mut InodeTable,
+    dir_inos: &mut HashMap<PathBuf, Ino>,
+    rel_path: &Path,
+    host_path: &Path,
+) -> PyResult<Ino> {
+    if let Some(&ino) = dir_inos.get(rel_path) {
+        return Ok(ino);
+    }
+
+    let parent_rel = rel_path.parent().unwrap_or(Path::new("")).to_path_buf();
+    let parent_ino = ensure_dir(py, inodes, dir_inos, &parent_rel, host_path)?;
+
+    let name = rel_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mode = std::fs::metadata(host_path.join(rel_path))
+        .map(|m| (m.permissions().mode() & 0o7777) as u16)
+        .unwrap_or(0o755);
+
+    let dir_py = Py::new(py, PyDirectory::new(name, mode))?;
+    let ino = inodes.insert_dir(py, parent_ino, dir_py)?;
+    dir_inos.insert(rel_path.to_path_buf(), ino);
+    Ok(ino)
+}
+
+/// Walk `host_path` and mirror its files, directories, and symlinks into
+/// `inodes` under `parent_ino`, applying `include`/`exclude` glob filters.
+pub fn import_dir(
+    py: Python<'_>,
+    inodes: &mut InodeTable,
+    parent_ino: Ino,
+    host_path: &Path,
+    include: &[String],
+    exclude: &[String],
+) -> PyResult<()> {
+    let mut dir_inos = HashMap::new();
+    dir_inos.insert(PathBuf::new(), parent_ino);
+
+    let mut entries = Vec::new();
+    collect_entries(host_path, Path::new(""), &mut entries).map_err(|e| {
+        PyOSError::new_err(format!("Failed to read {}: {}", host_path.display(), e))
+    })?;
+
+    for rel_path in entries {
+        let rel_str = rel_path.to_string_lossy().replace('\\', "/");
+        if !is_included(&rel_str, include, exclude) {
+            continue;
+        }
+
+        let full_path = host_path.join(&rel_path);
+        let metadata = std::fs::symlink_metadata(&full_path).map_err(|e| {
+            PyOSError::new_err(format!("Failed to stat {}: {}", full_path.display(), e))
+        })?;
+
+        if metadata.is_dir() {
+            ensure_dir(py, inodes, &mut dir_inos, &rel_path, host_path)?;
+            continue;
+        }
+
+        let parent_rel = rel_path.parent().unwrap_or(Path::new("")).to_path_buf();
+        let parent_ino = ensure_dir(py, inodes, &mut dir_inos, &parent_rel, host_path)?;
+        let name = rel_path.file_name().unwrap().to_string_lossy().to_string();
+
+        if metadata.file_type().is_symlink() {
+            let target = std::fs::read_link(&full_path).map_err(|e| {
+                PyOSError::new_err(format!(
+                    "Failed to read symlink {}: {}",
+                    full_path.display(),
+                    e
+                ))
+            })?;
+            let symlink_py =
+                Py::new(py, PySymlink::new(name, target.to_string_lossy().to_string()))?;
+            inodes.insert_symlink(py, parent_ino, symlink_py)?;
+        } else {
+            let content = std::fs::read(&full_path).map_err(|e| {
+                PyOSError::new_err(format!("Failed to read {}: {}", full_path.display(), e))
+            })?;
+            let mode = (metadata.permissions().mode() & 0o7777) as u16;
+            let file_py = Py::new(py, PyFile::new(py, name, Some(&content), mode)?)?;
+            inodes.insert_file(py, parent_ino, file_py)?;
+        }
+    }
+
+    Ok(())
+}
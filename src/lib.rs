@@ -1,10 +1,16 @@
+mod chunk;
+mod docket;
 mod fs;
+mod import;
+mod overlay;
 mod pytypes;
+mod snapshot;
 mod tree;
+mod walk;
 
 use pyo3::prelude::*;
 use pytypes::{PyFilesystem, PyMountHandle};
-use tree::{PyDirectory, PyFile, PySymlink};
+use tree::{PyDirectory, PyFile, PyLazyDirectory, PySpecial, PySymlink};
 
 /// A Python module for mounting Python-owned memory as a FUSE filesystem
 #[pymodule]
@@ -16,6 +22,8 @@ fn _pyrofs(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<PyFile>()?;
     m.add_class::<PyDirectory>()?;
     m.add_class::<PySymlink>()?;
+    m.add_class::<PySpecial>()?;
+    m.add_class::<PyLazyDirectory>()?;
     m.add_class::<PyMountHandle>()?;
 
     Ok(())
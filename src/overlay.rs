@@ -0,0 +1,246 @@
+//! Overlayfs-style read-only lower layer for [`MemFs`](crate::fs::MemFs).
+//!
+//! The in-memory `InodeTable` is always the upper, writable layer. When a
+//! `lower_dir` is configured, lookups that miss the upper tree fall through
+//! to the backing host directory and are served from a synthetic inode;
+//! writing to one of those copies it into the upper tree first (copy-up).
+//! Removing an entry that only exists in the lower layer is recorded as a
+//! whiteout so it disappears from the merged view without touching the
+//! host files.
+use crate::tree::{Ino, InodeTable, PyDirectory, PyFile, ROOT_INO};
+use parking_lot::Mutex;
+use pyo3::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Synthetic inodes for lower-only entries start here, well above anything
+/// `InodeTable` itself will ever allocate, so the two spaces never collide.
+const LOWER_INO_BASE: Ino = 1 << 32;
+
+pub struct Overlay {
+    lower_dir: Option<PathBuf>,
+    /// Relative (to `lower_dir`) path for every inode — upper directory or
+    /// synthetic lower-only node — that has a counterpart in the lower layer.
+    lower_paths: Mutex<HashMap<Ino, PathBuf>>,
+    /// Reverse of `lower_paths`, but only for synthetic lower-only nodes:
+    /// memoizes the inode already allocated for a lower-relative path so
+    /// repeated `lookup_lower` calls return the same `ino` instead of
+    /// minting a fresh one every time (which would break `st_ino` identity,
+    /// hardlink detection, and caches across repeated lookups).
+    lower_inos: Mutex<HashMap<PathBuf, Ino>>,
+    /// Redirects a synthetic lower-only ino to the upper ino it was copied
+    /// up into, so a kernel-cached `ino` keeps working across copy-up.
+    aliases: Mutex<HashMap<Ino, Ino>>,
+    /// `(parent_ino, name)` dentries deleted while only present in the lower
+    /// layer; hidden from the merged view without touching the host files.
+    whiteouts: Mutex<HashSet<(Ino, String)>>,
+    next_lower_ino: AtomicU64,
+}
+
+impl Overlay {
+    pub fn new(lower_dir: Option<PathBuf>) -> Self {
+        let lower_paths = Mutex::new(HashMap::new());
+        if lower_dir.is_some() {
+            lower_paths.lock().insert(ROOT_INO, PathBuf::new());
+        }
+        Self {
+            lower_dir,
+            lower_paths,
+            lower_inos: Mutex::new(HashMap::new()),
+            aliases: Mutex::new(HashMap::new()),
+            whiteouts: Mutex::new(HashSet::new()),
+            next_lower_ino: AtomicU64::new(LOWER_INO_BASE),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn is_active(&self) -> bool {
+        self.lower_dir.is_some()
+    }
+
+    /// Follow a copy-up alias, if `ino` was a lower-only inode that has
+    /// since been materialized in the upper tree.
+    pub fn resolve(&self, ino: Ino) -> Ino {
+        self.aliases.lock().get(&ino).copied().unwrap_or(ino)
+    }
+
+    /// The lower-relative path registered for `ino` (an upper directory
+    /// that mirrors a lower one, or a synthetic lower-only node).
+    pub fn lower_path_for(&self, ino: Ino) -> Option<PathBuf> {
+        self.lower_paths.lock().get(&ino).cloned()
+    }
+
+    fn register_lower_path(&self, ino: Ino, rel: PathBuf) {
+        self.lower_paths.lock().insert(ino, rel);
+    }
+
+    fn alloc_lower_ino(&self) -> Ino {
+        self.next_lower_ino.fetch_add(1, Ordering::SeqCst)
+    }
+
+    pub fn is_whiteout(&self, parent: Ino, name: &str) -> bool {
+        self.whiteouts.lock().contains(&(parent, name.to_string()))
+    }
+
+    pub fn add_whiteout(&self, parent: Ino, name: &str) {
+        self.whiteouts.lock().insert((parent, name.to_string()));
+    }
+
+    /// Look up `name` under `parent` purely in the lower layer (no upper
+    /// entry exists for it), allocating a synthetic inode for it if found.
+    /// A given lower-relative path always maps to the same inode, memoized
+    /// across calls, so repeated lookups don't mint a new identity each time.
+    pub fn lookup_lower(&self, parent: Ino, name: &str) -> Option<(Ino, PathBuf, std::fs::Metadata)> {
+        let lower_dir = self.lower_dir.as_ref()?;
+        let parent_rel = self.lower_path_for(parent)?;
+        let rel = parent_rel.join(name);
+        let full_path = lower_dir.join(&rel);
+        let metadata = std::fs::symlink_metadata(&full_path).ok()?;
+        let ino = match self.lower_inos.lock().entry(rel.clone()) {
+            std::collections::hash_map::Entry::Occupied(e) => *e.get(),
+            std::collections::hash_map::Entry::Vacant(e) => {
+                let ino = self.alloc_lower_ino();
+                e.insert(ino);
+                self.register_lower_path(ino, rel);
+                ino
+            }
+        };
+        Some((ino, full_path, metadata))
+    }
+
+    /// List the names of entries directly inside the lower directory that
+    /// `ino` mirrors (used for `readdir` on both lower-only directories and
+    /// upper directories that also have a lower counterpart).
+    pub fn list_lower(&self, ino: Ino) -> Vec<String> {
+        let Some(path) = self.read_lower(ino) else {
+            return Vec::new();
+        };
+        let Ok(entries) = std::fs::read_dir(&path) else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(|e| e.ok().map(|e| e.file_name().to_string_lossy().to_string()))
+            .collect()
+    }
+
+    /// Whether `name` under `parent` also exists in the lower layer,
+    /// independent of whether it's currently shadowed by an upper entry.
+    pub fn lower_child_exists(&self, parent: Ino, name: &str) -> bool {
+        match (&self.lower_dir, self.lower_path_for(parent)) {
+            (Some(lower_dir), Some(parent_rel)) => {
+                lower_dir.join(parent_rel.join(name)).symlink_metadata().is_ok()
+            }
+            _ => false,
+        }
+    }
+
+    /// The host path of the lower-layer entry that `ino` mirrors (used to
+    /// serve `read` and `getattr` for entries that haven't been copied up).
+    /// Doesn't check that the path still exists.
+    pub fn read_lower(&self, ino: Ino) -> Option<std::path::PathBuf> {
+        let lower_dir = self.lower_dir.as_ref()?;
+        let rel = self.lower_path_for(ino)?;
+        Some(lower_dir.join(rel))
+    }
+
+    /// Copy a lower-only file up into the upper tree (creating any missing
+    /// ancestor directories along the way) and register an alias so the
+    /// kernel's existing `ino` keeps resolving to it. Returns the new upper
+    /// inode.
+    pub fn copy_up_file(&self, py: Python<'_>, inodes: &mut InodeTable, ino: Ino) -> PyResult<Ino> {
+        if let Some(existing) = self.aliases.lock().get(&ino) {
+            return Ok(*existing);
+        }
+
+        let lower_dir = self
+            .lower_dir
+            .clone()
+            .ok_or_else(|| pyo3::exceptions::PyRuntimeError::new_err("No lower directory"))?;
+        let rel = self.lower_path_for(ino).ok_or_else(|| {
+            pyo3::exceptions::PyRuntimeError::new_err("Unknown lower inode")
+        })?;
+
+        let parent_rel = rel.parent().unwrap_or(Path::new("")).to_path_buf();
+        let parent_ino = self.copy_up_ancestors(py, inodes, &lower_dir, &parent_rel)?;
+        let name = rel
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let full_path = lower_dir.join(&rel);
+        let content = std::fs::read(&full_path).map_err(|e| {
+            pyo3::exceptions::PyOSError::new_err(format!(
+                "Failed to copy up {}: {}",
+                full_path.display(),
+                e
+            ))
+        })?;
+        let mode = std::fs::metadata(&full_path)
+            .map(|m| (m.permissions().mode() & 0o7777) as u16)
+            .unwrap_or(0o644);
+
+        let file_py = Py::new(py, PyFile::new(py, name, Some(&content), mode)?)?;
+        let new_ino = inodes.insert_file(py, parent_ino, file_py)?;
+        self.aliases.lock().insert(ino, new_ino);
+        Ok(new_ino)
+    }
+
+    /// Ensure the upper tree has directories mirroring `rel` (relative to
+    /// the lower root), creating any that are missing, and return the final
+    /// directory's upper inode.
+    fn copy_up_ancestors(
+        &self,
+        py: Python<'_>,
+        inodes: &mut InodeTable,
+        lower_dir: &Path,
+        rel: &Path,
+    ) -> PyResult<Ino> {
+        let mut parent_ino = ROOT_INO;
+        let mut acc = PathBuf::new();
+        for component in rel.components() {
+            let name = component.as_os_str().to_string_lossy().to_string();
+            acc.push(&name);
+            if let Some(existing) = inodes.lookup(py, parent_ino, &name) {
+                parent_ino = existing;
+                continue;
+            }
+            let mode = std::fs::metadata(lower_dir.join(&acc))
+                .map(|m| (m.permissions().mode() & 0o7777) as u16)
+                .unwrap_or(0o755);
+            let dir_py = Py::new(py, PyDirectory::new(name, mode))?;
+            let new_ino = inodes.insert_dir(py, parent_ino, dir_py)?;
+            self.register_lower_path(new_ino, acc.clone());
+            parent_ino = new_ino;
+        }
+        Ok(parent_ino)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_lower_memoizes_the_same_ino_across_repeated_calls() {
+        let dir = std::env::temp_dir().join(format!(
+            "pyrofs-overlay-test-{}-{:?}",
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("hello.txt"), b"hi").unwrap();
+
+        let overlay = Overlay::new(Some(dir.clone()));
+        let (first_ino, _, _) = overlay.lookup_lower(ROOT_INO, "hello.txt").unwrap();
+        let (second_ino, _, _) = overlay.lookup_lower(ROOT_INO, "hello.txt").unwrap();
+        assert_eq!(
+            first_ino, second_ino,
+            "repeated lookups of the same lower path must return the same ino"
+        );
+        assert_eq!(overlay.lower_inos.lock().len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,5 +1,5 @@
 use crate::fs::MemFs;
-use crate::tree::{InodeTable, NodeRef, PyDirectory, PyFile, PySymlink, ROOT_INO};
+use crate::tree::{InodeTable, NodeRef, PyDirectory, PyFile, PyLazyDirectory, PySymlink, ROOT_INO};
 use fuser::MountOption;
 use parking_lot::Mutex;
 use pyo3::exceptions::{PyOSError, PyRuntimeError, PyValueError};
@@ -62,11 +62,12 @@ pub struct PyFilesystem {
 #[pymethods]
 impl PyFilesystem {
     #[new]
-    fn new(py: Python<'_>) -> PyResult<Self> {
+    #[pyo3(signature = (read_only=false))]
+    fn new(py: Python<'_>, read_only: bool) -> PyResult<Self> {
         let uid = unsafe { libc::getuid() };
         let gid = unsafe { libc::getgid() };
 
-        let mut table = InodeTable::new(uid, gid);
+        let mut table = InodeTable::new(uid, gid, read_only);
         let root = table.init_root(py)?;
 
         Ok(Self {
@@ -81,6 +82,70 @@ impl PyFilesystem {
         self.root.clone_ref(py)
     }
 
+    /// Whether mutating operations (creating, writing, removing, renaming)
+    /// are currently rejected.
+    #[getter(read_only)]
+    fn get_read_only(&self) -> bool {
+        self.inodes.lock().is_read_only()
+    }
+
+    /// Flip the read-only flag, e.g. to serve an in-memory tree as a
+    /// guaranteed-immutable view after it's been populated.
+    #[setter(read_only)]
+    fn set_read_only(&self, read_only: bool) {
+        self.inodes.lock().set_read_only(read_only);
+    }
+
+    /// Load a filesystem previously written by `save`
+    #[staticmethod]
+    #[pyo3(signature = (path, read_only=false))]
+    fn load(py: Python<'_>, path: &str, read_only: bool) -> PyResult<Self> {
+        let data = std::fs::read(path)
+            .map_err(|e| PyOSError::new_err(format!("Failed to read {}: {}", path, e)))?;
+        let snapshot = crate::snapshot::decode(&data)
+            .map_err(|e| PyValueError::new_err(format!("Corrupt snapshot {}: {}", path, e)))?;
+        let (mut table, root) = InodeTable::from_snapshot(py, snapshot)?;
+        table.set_read_only(read_only);
+
+        Ok(Self {
+            inodes: Arc::new(Mutex::new(table)),
+            root,
+        })
+    }
+
+    /// Snapshot the whole filesystem to a single compressed file, for later
+    /// reconstruction via `load`
+    fn save(&self, py: Python<'_>, path: &str) -> PyResult<()> {
+        let snapshot = self.inodes.lock().to_snapshot(py);
+        let data = crate::snapshot::encode(&snapshot)
+            .map_err(|e| PyRuntimeError::new_err(format!("Failed to encode snapshot: {}", e)))?;
+        std::fs::write(path, data)
+            .map_err(|e| PyOSError::new_err(format!("Failed to write {}: {}", path, e)))
+    }
+
+    /// Load a filesystem previously written by `dump`, in the append-only
+    /// docket format (distinct from `save`/`load`'s compressed snapshot).
+    #[staticmethod]
+    #[pyo3(signature = (path, read_only=false))]
+    fn load_docket(py: Python<'_>, path: &str, read_only: bool) -> PyResult<Self> {
+        let (mut table, root) = InodeTable::load(py, std::path::Path::new(path))?;
+        table.set_read_only(read_only);
+        Ok(Self {
+            inodes: Arc::new(Mutex::new(table)),
+            root,
+        })
+    }
+
+    /// Write the whole filesystem to `path` in a Mercurial dirstate-v2-style
+    /// docket format: a fixed header plus fixed-width node records and a
+    /// trailing blob section, for later reconstruction via `load_docket`.
+    fn dump(&self, py: Python<'_>, path: &str) -> PyResult<()> {
+        self.inodes
+            .lock()
+            .dump(py, std::path::Path::new(path))
+            .map_err(|e| PyOSError::new_err(format!("Failed to write {}: {}", path, e)))
+    }
+
     /// Create a file in the filesystem
     #[pyo3(signature = (path, content=None, mode=0o644))]
     fn create_file(
@@ -155,7 +220,9 @@ impl PyFilesystem {
                     Some(NodeRef::Dir(_)) => {
                         current_ino = child_ino;
                     }
-                    Some(NodeRef::File(_)) | Some(NodeRef::Symlink(_)) => {
+                    Some(NodeRef::File(_))
+                    | Some(NodeRef::Symlink(_))
+                    | Some(NodeRef::Special(_)) => {
                         return Err(PyValueError::new_err(format!(
                             "Path component is a file, not a directory: {}",
                             part
@@ -189,6 +256,45 @@ impl PyFilesystem {
         }
     }
 
+    /// Create a directory whose contents are populated on demand by
+    /// `lazy`'s `on_lookup`/`on_list` callbacks instead of being supplied
+    /// up front. See `LazyDirectory` for the callback signatures.
+    fn create_lazy_dir(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        lazy: Py<PyLazyDirectory>,
+    ) -> PyResult<Py<PyDirectory>> {
+        let (parent_ino, name) = self.resolve_parent(py, path)?;
+
+        let mut inodes = self.inodes.lock();
+
+        if inodes.lookup(py, parent_ino, name).is_some() {
+            return Err(PyValueError::new_err(format!(
+                "Path already exists: {}",
+                path
+            )));
+        }
+
+        lazy.borrow_mut(py).name = name.to_string();
+        let ino = inodes.insert_lazy_dir(py, parent_ino, lazy)?;
+        match inodes.get_dir(ino) {
+            Some(d) => Ok(d.clone_ref(py)),
+            None => Err(PyRuntimeError::new_err(
+                "Internal error: directory not found",
+            )),
+        }
+    }
+
+    /// Evict a lazy directory's cached entries so the next lookup or
+    /// listing re-consults its callbacks. A no-op if `path` isn't a lazy
+    /// directory.
+    fn invalidate(&self, py: Python<'_>, path: &str) -> PyResult<()> {
+        let ino = self.resolve_path(py, path)?;
+        self.inodes.lock().invalidate_lazy_dir(py, ino);
+        Ok(())
+    }
+
     /// Get a file or directory by path
     fn get(&self, py: Python<'_>, path: &str) -> PyResult<Py<PyAny>> {
         let ino = self.resolve_path(py, path)?;
@@ -198,6 +304,7 @@ impl PyFilesystem {
             Some(NodeRef::File(f)) => Ok(f.clone_ref(py).into_any()),
             Some(NodeRef::Dir(d)) => Ok(d.clone_ref(py).into_any()),
             Some(NodeRef::Symlink(s)) => Ok(s.clone_ref(py).into_any()),
+            Some(NodeRef::Special(s)) => Ok(s.clone_ref(py).into_any()),
             None => Err(PyValueError::new_err(format!("Path not found: {}", path))),
         }
     }
@@ -228,6 +335,26 @@ impl PyFilesystem {
         Ok(symlink_py)
     }
 
+    /// Create a hard link: a second directory entry at `new_path` pointing
+    /// at the same underlying file as `existing_path`. Writes through
+    /// either name are visible through both, and the file's bytes are only
+    /// reclaimed once every linked name has been removed.
+    fn link(&self, py: Python<'_>, existing_path: &str, new_path: &str) -> PyResult<()> {
+        let ino = self.resolve_path(py, existing_path)?;
+        let (new_parent_ino, new_name) = self.resolve_parent(py, new_path)?;
+
+        let mut inodes = self.inodes.lock();
+
+        if inodes.lookup(py, new_parent_ino, new_name).is_some() {
+            return Err(PyValueError::new_err(format!(
+                "Path already exists: {}",
+                new_path
+            )));
+        }
+
+        inodes.link(py, ino, new_parent_ino, new_name)
+    }
+
     /// Read the target of a symbolic link
     fn readlink(&self, py: Python<'_>, path: &str) -> PyResult<String> {
         let ino = self.resolve_path(py, path)?;
@@ -251,12 +378,16 @@ impl PyFilesystem {
 
     /// Remove a file or symlink
     fn remove_file(&self, py: Python<'_>, path: &str) -> PyResult<()> {
-        let ino = self.resolve_path(py, path)?;
+        let (parent_ino, name) = self.resolve_parent(py, path)?;
         let mut inodes = self.inodes.lock();
 
+        let ino = inodes
+            .lookup(py, parent_ino, name)
+            .ok_or_else(|| PyValueError::new_err(format!("Path not found: {}", path)))?;
+
         match inodes.get(ino) {
-            Some(NodeRef::File(_)) | Some(NodeRef::Symlink(_)) => {
-                inodes.remove(py, ino)?;
+            Some(NodeRef::File(_)) | Some(NodeRef::Symlink(_)) | Some(NodeRef::Special(_)) => {
+                inodes.remove(py, parent_ino, name)?;
                 Ok(())
             }
             Some(NodeRef::Dir(_)) => Err(PyValueError::new_err("Path is a directory")),
@@ -266,18 +397,22 @@ impl PyFilesystem {
 
     /// Remove a directory (must be empty)
     fn remove_dir(&self, py: Python<'_>, path: &str) -> PyResult<()> {
-        let ino = self.resolve_path(py, path)?;
+        let (parent_ino, name) = self.resolve_parent(py, path)?;
         let mut inodes = self.inodes.lock();
 
+        let ino = inodes
+            .lookup(py, parent_ino, name)
+            .ok_or_else(|| PyValueError::new_err(format!("Path not found: {}", path)))?;
+
         match inodes.get(ino) {
             Some(NodeRef::Dir(d)) => {
                 if !d.borrow(py).children.is_empty() {
                     return Err(PyValueError::new_err("Directory not empty"));
                 }
-                inodes.remove(py, ino)?;
+                inodes.remove(py, parent_ino, name)?;
                 Ok(())
             }
-            Some(NodeRef::File(_)) | Some(NodeRef::Symlink(_)) => {
+            Some(NodeRef::File(_)) | Some(NodeRef::Symlink(_)) | Some(NodeRef::Special(_)) => {
                 Err(PyValueError::new_err("Path is a file, not a directory"))
             }
             None => Err(PyValueError::new_err(format!("Path not found: {}", path))),
@@ -287,6 +422,7 @@ impl PyFilesystem {
     /// List contents of a directory
     fn listdir(&self, py: Python<'_>, path: &str) -> PyResult<Vec<String>> {
         let ino = self.resolve_path(py, path)?;
+        InodeTable::ensure_lazy_listed_unlocked(&self.inodes, py, ino)?;
         let inodes = self.inodes.lock();
 
         match inodes.get_dir(ino) {
@@ -295,9 +431,53 @@ impl PyFilesystem {
         }
     }
 
-    /// Mount the filesystem at the given path
-    #[pyo3(signature = (mount_point, allow_other=false))]
-    fn mount(&self, mount_point: &str, allow_other: bool) -> PyResult<PyMountHandle> {
+    /// Recursively walk the tree rooted at `path`, yielding one
+    /// `(dirpath, dirnames, filenames)` tuple per directory, like `os.walk`.
+    /// A directory that can't be read is skipped rather than aborting the
+    /// rest of the walk. Symlinks to directories are only descended into
+    /// when `follow_symlinks` is set.
+    #[pyo3(signature = (path="/", follow_symlinks=false))]
+    fn walk(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        follow_symlinks: bool,
+    ) -> PyResult<Vec<(String, Vec<String>, Vec<String>)>> {
+        let ino = self.resolve_path(py, path)?;
+        let inodes = self.inodes.lock();
+
+        if inodes.get_dir(ino).is_none() {
+            return Err(PyValueError::new_err("Path is not a directory"));
+        }
+
+        let root_path = path.trim_matches('/').to_string();
+        Ok(crate::walk::walk(py, &inodes, ino, root_path, follow_symlinks)
+            .into_iter()
+            .map(|e| (e.path, e.dirnames, e.filenames))
+            .collect())
+    }
+
+    /// Return all paths matching a shell glob `pattern` (`*`/`?` match
+    /// within a path segment, `**` matches any number of segments).
+    fn glob(&self, py: Python<'_>, pattern: &str) -> Vec<String> {
+        let inodes = self.inodes.lock();
+        crate::walk::glob(py, &inodes, pattern)
+    }
+
+    /// Mount the filesystem at the given path. If `lower_dir` is given, it's
+    /// merged in underneath the in-memory tree as a read-only base layer,
+    /// overlayfs-style: lookups that miss the in-memory tree fall through to
+    /// it, a file is copied into memory the first time it's written to, and
+    /// deleting a lower-only entry hides it from the merged view (a
+    /// "whiteout") without touching the files backing `lower_dir`.
+    #[pyo3(signature = (mount_point, allow_other=false, read_only=false, lower_dir=None))]
+    fn mount(
+        &self,
+        mount_point: &str,
+        allow_other: bool,
+        read_only: bool,
+        lower_dir: Option<&str>,
+    ) -> PyResult<PyMountHandle> {
         let mount_path = PathBuf::from(mount_point);
 
         // Ensure mount point exists
@@ -308,7 +488,17 @@ impl PyFilesystem {
             )));
         }
 
-        let fs = MemFs::new(Arc::clone(&self.inodes));
+        let lower_dir = lower_dir.map(PathBuf::from);
+        if let Some(lower_dir) = &lower_dir
+            && !lower_dir.is_dir()
+        {
+            return Err(PyOSError::new_err(format!(
+                "Lower directory does not exist: {}",
+                lower_dir.display()
+            )));
+        }
+
+        let fs = MemFs::new(Arc::clone(&self.inodes), read_only, lower_dir);
 
         let mut options = vec![
             MountOption::FSName("pyrofs".to_string()),
@@ -320,6 +510,10 @@ impl PyFilesystem {
             options.push(MountOption::AllowOther);
         }
 
+        if read_only {
+            options.push(MountOption::RO);
+        }
+
         let session = fuser::spawn_mount2(fs, &mount_path, &options)
             .map_err(|e| PyOSError::new_err(format!("Failed to mount filesystem: {}", e)))?;
 
@@ -329,6 +523,31 @@ impl PyFilesystem {
         })
     }
 
+    /// Recursively import files, directories, and symlinks from a real
+    /// directory on disk into the filesystem root, preserving modes.
+    /// `include`/`exclude` are lists of gitignore-style glob patterns
+    /// (matched against paths relative to `host_path`); an `exclude` entry
+    /// prefixed with `!` re-includes anything an earlier, coarser pattern
+    /// excluded.
+    #[pyo3(signature = (host_path, include=None, exclude=None))]
+    fn import_dir(
+        &self,
+        py: Python<'_>,
+        host_path: &str,
+        include: Option<Vec<String>>,
+        exclude: Option<Vec<String>>,
+    ) -> PyResult<()> {
+        let mut inodes = self.inodes.lock();
+        crate::import::import_dir(
+            py,
+            &mut inodes,
+            ROOT_INO,
+            std::path::Path::new(host_path),
+            &include.unwrap_or_default(),
+            &exclude.unwrap_or_default(),
+        )
+    }
+
     /// Rename/move a file or directory
     fn rename(&self, py: Python<'_>, old_path: &str, new_path: &str) -> PyResult<()> {
         let (old_parent_ino, old_name) = self.resolve_parent(py, old_path)?;
@@ -364,7 +583,7 @@ impl PyFilesystem {
             }
 
             // Remove the destination
-            inodes.remove(py, existing_ino)?;
+            inodes.remove(py, new_parent_ino, new_name)?;
         }
 
         inodes.rename(py, old_parent_ino, old_name, new_parent_ino, new_name)
@@ -389,11 +608,11 @@ impl PyFilesystem {
             return Ok(ROOT_INO);
         }
 
-        let inodes = self.inodes.lock();
         let mut current = ROOT_INO;
 
         for part in parts {
-            match inodes.lookup(py, current, part) {
+            InodeTable::ensure_lazy_entry_unlocked(&self.inodes, py, current, part)?;
+            match self.inodes.lock().lookup(py, current, part) {
                 Some(ino) => current = ino,
                 None => {
                     return Err(PyValueError::new_err(format!("Path not found: {}", path)));
@@ -0,0 +1,134 @@
+//! On-disk snapshot format for `InodeTable`: a bincode-encoded node graph
+//! wrapped in zstd compression, used by `MemFS.save`/`MemFS.load` to
+//! persist a whole tree as a single file.
+use crate::tree::{FileKind, Ino};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+/// Bumped whenever the on-disk layout changes incompatibly.
+pub(crate) const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, PartialEq)]
+pub(crate) struct SnapshotFile {
+    pub name: String,
+    pub content: Vec<u8>,
+    pub mode: u16,
+    pub uid: u32,
+    pub gid: u32,
+    pub xattrs: HashMap<String, Vec<u8>>,
+    pub parent_ino: Ino,
+    pub atime: SystemTime,
+    pub mtime: SystemTime,
+    pub ctime: SystemTime,
+}
+
+#[derive(Serialize, Deserialize, PartialEq)]
+pub(crate) struct SnapshotDir {
+    pub name: String,
+    pub mode: u16,
+    pub uid: u32,
+    pub gid: u32,
+    pub xattrs: HashMap<String, Vec<u8>>,
+    pub parent_ino: Ino,
+    pub children: HashMap<String, Ino>,
+    pub atime: SystemTime,
+    pub mtime: SystemTime,
+    pub ctime: SystemTime,
+}
+
+#[derive(Serialize, Deserialize, PartialEq)]
+pub(crate) struct SnapshotSymlink {
+    pub name: String,
+    pub target: String,
+    pub uid: u32,
+    pub gid: u32,
+    pub xattrs: HashMap<String, Vec<u8>>,
+    pub parent_ino: Ino,
+    pub atime: SystemTime,
+    pub mtime: SystemTime,
+    pub ctime: SystemTime,
+}
+
+/// Mirrors `FileKind`'s four device-node variants; `File`/`Directory`/`Symlink`
+/// never appear here since those kinds get their own `Snapshot*` struct.
+#[derive(Serialize, Deserialize, PartialEq)]
+pub(crate) enum SnapshotDeviceKind {
+    NamedPipe,
+    Socket,
+    CharDevice,
+    BlockDevice,
+}
+
+impl From<FileKind> for SnapshotDeviceKind {
+    fn from(kind: FileKind) -> Self {
+        match kind {
+            FileKind::NamedPipe => SnapshotDeviceKind::NamedPipe,
+            FileKind::Socket => SnapshotDeviceKind::Socket,
+            FileKind::CharDevice => SnapshotDeviceKind::CharDevice,
+            FileKind::BlockDevice => SnapshotDeviceKind::BlockDevice,
+            FileKind::File | FileKind::Directory | FileKind::Symlink => {
+                unreachable!("non-device FileKind passed to SnapshotDeviceKind")
+            }
+        }
+    }
+}
+
+impl From<SnapshotDeviceKind> for FileKind {
+    fn from(kind: SnapshotDeviceKind) -> Self {
+        match kind {
+            SnapshotDeviceKind::NamedPipe => FileKind::NamedPipe,
+            SnapshotDeviceKind::Socket => FileKind::Socket,
+            SnapshotDeviceKind::CharDevice => FileKind::CharDevice,
+            SnapshotDeviceKind::BlockDevice => FileKind::BlockDevice,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq)]
+pub(crate) struct SnapshotSpecial {
+    pub name: String,
+    pub kind: SnapshotDeviceKind,
+    pub mode: u16,
+    pub uid: u32,
+    pub gid: u32,
+    pub rdev: u32,
+    pub xattrs: HashMap<String, Vec<u8>>,
+    pub parent_ino: Ino,
+    pub atime: SystemTime,
+    pub mtime: SystemTime,
+    pub ctime: SystemTime,
+}
+
+#[derive(Serialize, Deserialize, PartialEq)]
+pub(crate) enum SnapshotNode {
+    File(SnapshotFile),
+    Dir(SnapshotDir),
+    Symlink(SnapshotSymlink),
+    Special(SnapshotSpecial),
+}
+
+/// Whole-tree snapshot: a format version and root inode for validation,
+/// plus every inode keyed by its number.
+#[derive(Serialize, Deserialize, PartialEq)]
+pub(crate) struct Snapshot {
+    pub version: u32,
+    pub root_ino: Ino,
+    pub next_ino: Ino,
+    pub uid: u32,
+    pub gid: u32,
+    pub link_counts: HashMap<Ino, u32>,
+    pub nodes: HashMap<Ino, SnapshotNode>,
+}
+
+/// Encode a snapshot as bincode wrapped in zstd compression.
+pub(crate) fn encode(snapshot: &Snapshot) -> Result<Vec<u8>, String> {
+    let bytes = bincode::serialize(snapshot).map_err(|e| e.to_string())?;
+    zstd::encode_all(bytes.as_slice(), 0).map_err(|e| e.to_string())
+}
+
+/// Decode a snapshot previously produced by [`encode`].
+pub(crate) fn decode(data: &[u8]) -> Result<Snapshot, String> {
+    let bytes = zstd::decode_all(data).map_err(|e| e.to_string())?;
+    bincode::deserialize(&bytes).map_err(|e| e.to_string())
+}
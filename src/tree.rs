@@ -1,6 +1,13 @@
+use crate::chunk::{ChunkHash, ChunkStore};
+use crate::snapshot::{
+    Snapshot, SnapshotDir, SnapshotFile, SnapshotNode, SnapshotSpecial, SnapshotSymlink,
+};
+use parking_lot::Mutex;
 use pyo3::prelude::*;
 use pyo3::types::PyBytes;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::SystemTime;
 
 /// Unique inode identifier
@@ -9,6 +16,14 @@ pub type Ino = u64;
 /// Root inode is always 1 in FUSE
 pub const ROOT_INO: Ino = 1;
 
+/// Error returned by a mutating [`InodeTable`] or [`PyFile`] method when the
+/// table is read-only; a distinct, consistently-worded `OSError` so the FUSE
+/// layer can map it to `EROFS` the same way it already does for a mount
+/// opened with its own `read_only` flag.
+fn read_only_error() -> PyErr {
+    pyo3::exceptions::PyOSError::new_err("Filesystem is read-only")
+}
+
 /// File attributes mirroring stat(2)
 #[derive(Clone, Debug)]
 pub struct FileAttr {
@@ -24,6 +39,7 @@ pub struct FileAttr {
     pub nlink: u32,
     pub uid: u32,
     pub gid: u32,
+    pub rdev: u32,
 }
 
 impl FileAttr {
@@ -43,6 +59,7 @@ impl FileAttr {
             nlink: 1,
             uid,
             gid,
+            rdev: 0,
         }
     }
 
@@ -62,6 +79,7 @@ impl FileAttr {
             nlink: 2,
             uid,
             gid,
+            rdev: 0,
         }
     }
 }
@@ -71,6 +89,10 @@ pub enum FileKind {
     File,
     Directory,
     Symlink,
+    NamedPipe,
+    Socket,
+    CharDevice,
+    BlockDevice,
 }
 
 /// Reference to a Python-owned node
@@ -78,15 +100,17 @@ pub enum NodeRef {
     File(Py<PyFile>),
     Dir(Py<PyDirectory>),
     Symlink(Py<PySymlink>),
+    Special(Py<PySpecial>),
 }
 
 impl NodeRef {
     #[allow(dead_code)]
-    pub fn kind(&self) -> FileKind {
+    pub fn kind(&self, py: Python<'_>) -> FileKind {
         match self {
             NodeRef::File(_) => FileKind::File,
             NodeRef::Dir(_) => FileKind::Directory,
             NodeRef::Symlink(_) => FileKind::Symlink,
+            NodeRef::Special(s) => s.borrow(py).kind,
         }
     }
 
@@ -95,6 +119,7 @@ impl NodeRef {
         match self {
             NodeRef::File(f) => NodeRef::File(f.clone_ref(py)),
             NodeRef::Dir(d) => NodeRef::Dir(d.clone_ref(py)),
+            NodeRef::Special(s) => NodeRef::Special(s.clone_ref(py)),
             NodeRef::Symlink(s) => NodeRef::Symlink(s.clone_ref(py)),
         }
     }
@@ -105,10 +130,25 @@ impl NodeRef {
 pub struct PyFile {
     #[pyo3(get)]
     pub name: String,
-    #[pyo3(get, set)]
-    pub content: Py<PyBytes>,
+    /// Ordered content-defined chunks making up the file's bytes; see
+    /// `crate::chunk`. Shares entries with every other file in the same
+    /// `InodeTable` whose content happens to contain the same chunk.
+    pub(crate) chunks: Vec<ChunkHash>,
+    pub(crate) chunk_store: Arc<Mutex<ChunkStore>>,
+    /// Mirrors the owning `InodeTable`'s read-only flag; shared so that
+    /// flipping the table's flag after the file is inserted takes effect
+    /// immediately. See [`InodeTable::set_read_only`].
+    pub(crate) read_only: Arc<AtomicBool>,
     #[pyo3(get, set)]
     pub mode: u16,
+    #[pyo3(get, set)]
+    pub uid: u32,
+    #[pyo3(get, set)]
+    pub gid: u32,
+    /// Extended attributes (xattr), keyed by name; exposed to Python via
+    /// `get_xattr`/`set_xattr`/`list_xattr`/`remove_xattr` and to FUSE via
+    /// `InodeTable::{get,set,list,remove}xattr`.
+    pub(crate) xattrs: HashMap<String, Vec<u8>>,
     pub(crate) ino: Ino,
     pub(crate) parent_ino: Ino,
     pub(crate) mtime: SystemTime,
@@ -121,13 +161,27 @@ impl PyFile {
     #[new]
     #[pyo3(signature = (name, content=None, mode=0o644))]
     pub fn new(py: Python<'_>, name: String, content: Option<&[u8]>, mode: u16) -> PyResult<Self> {
-        let data = content.unwrap_or(b"");
-        let content = PyBytes::new(py, data).into();
+        // A file constructed standalone (not yet part of a filesystem) gets
+        // its own private chunk store; `InodeTable::insert_file` re-interns
+        // its chunks into the table's shared store once it's added, so
+        // dedup kicks in from that point on.
+        let chunk_store = Arc::new(Mutex::new(ChunkStore::new()));
+        let chunks = chunk_store
+            .lock()
+            .split_and_intern(py, content.unwrap_or(b""));
         let now = SystemTime::now();
         Ok(Self {
             name,
-            content,
+            chunks,
+            chunk_store,
+            // A standalone file isn't part of any table yet, so it's always
+            // mutable; `InodeTable::insert_file` rebinds this to the table's
+            // shared flag once it's added.
+            read_only: Arc::new(AtomicBool::new(false)),
             mode,
+            uid: 0,
+            gid: 0,
+            xattrs: HashMap::new(),
             ino: 0, // Assigned when added to filesystem
             parent_ino: 0,
             mtime: now,
@@ -136,38 +190,70 @@ impl PyFile {
         })
     }
 
+    /// Get an extended attribute's value, if set
+    fn get_xattr(&self, py: Python<'_>, name: &str) -> Option<Py<PyBytes>> {
+        self.xattrs.get(name).map(|v| PyBytes::new(py, v).into())
+    }
+
+    /// Set an extended attribute's value
+    fn set_xattr(&mut self, name: String, value: Vec<u8>) {
+        self.xattrs.insert(name, value);
+    }
+
+    /// List the names of all extended attributes
+    fn list_xattr(&self) -> Vec<String> {
+        self.xattrs.keys().cloned().collect()
+    }
+
+    /// Remove an extended attribute, returning whether it was present
+    fn remove_xattr(&mut self, name: &str) -> bool {
+        self.xattrs.remove(name).is_some()
+    }
+
     /// Get the size of the file in bytes
     #[getter]
     fn size(&self, py: Python<'_>) -> usize {
-        self.content.bind(py).as_bytes().len()
+        self.len(py)
+    }
+
+    /// Get the file's contents as bytes (reassembled from its chunks)
+    #[getter(content)]
+    fn get_content<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new(py, &self.assemble(py))
+    }
+
+    /// Replace the file's contents, rechunking and re-interning them
+    #[setter(content)]
+    fn set_content(&mut self, py: Python<'_>, data: &[u8]) -> PyResult<()> {
+        self.check_writable()?;
+        self.replace_content(py, data);
+        Ok(())
     }
 
     /// Read file contents as bytes
     fn read<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
-        self.content.bind(py).clone()
+        PyBytes::new(py, &self.assemble(py))
     }
 
     /// Write new contents to the file
     fn write(&mut self, py: Python<'_>, data: &[u8]) -> PyResult<()> {
-        self.content = PyBytes::new(py, data).into();
-        self.mtime = SystemTime::now();
-        self.ctime = SystemTime::now();
+        self.check_writable()?;
+        self.replace_content(py, data);
         Ok(())
     }
 
     /// Truncate the file to the given size
     pub fn truncate(&mut self, py: Python<'_>, size: usize) -> PyResult<()> {
-        let current = self.content.bind(py).as_bytes();
+        self.check_writable()?;
+        let current = self.assemble(py);
         let new_data = if size <= current.len() {
             current[..size].to_vec()
         } else {
-            let mut v = current.to_vec();
+            let mut v = current;
             v.resize(size, 0);
             v
         };
-        self.content = PyBytes::new(py, &new_data).into();
-        self.mtime = SystemTime::now();
-        self.ctime = SystemTime::now();
+        self.replace_content(py, &new_data);
         Ok(())
     }
 
@@ -175,12 +261,68 @@ impl PyFile {
         format!(
             "File(name={:?}, size={}, mode={:#o})",
             self.name,
-            self.content.bind(py).as_bytes().len(),
+            self.len(py),
             self.mode
         )
     }
 }
 
+impl PyFile {
+    /// Error to return from a mutating method if the owning `InodeTable` (or,
+    /// for a standalone file not yet part of one, this file itself) is
+    /// read-only.
+    pub(crate) fn check_writable(&self) -> PyResult<()> {
+        if self.read_only.load(Ordering::Relaxed) {
+            return Err(read_only_error());
+        }
+        Ok(())
+    }
+
+    /// Reassemble the file's chunks into one contiguous buffer.
+    pub(crate) fn assemble(&self, py: Python<'_>) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.len(py));
+        let store = self.chunk_store.lock();
+        for &hash in &self.chunks {
+            data.extend_from_slice(store.get(hash).bind(py).as_bytes());
+        }
+        data
+    }
+
+    /// Total size in bytes, summed across chunks without reassembling them.
+    pub(crate) fn len(&self, py: Python<'_>) -> usize {
+        let store = self.chunk_store.lock();
+        self.chunks.iter().map(|&hash| store.len(py, hash)).sum()
+    }
+
+    /// Release the current chunks, rechunk+intern `data`, and bump mtime/ctime.
+    pub(crate) fn replace_content(&mut self, py: Python<'_>, data: &[u8]) {
+        let mut store = self.chunk_store.lock();
+        store.release(&self.chunks);
+        self.chunks = store.split_and_intern(py, data);
+        drop(store);
+        self.mtime = SystemTime::now();
+        self.ctime = SystemTime::now();
+    }
+
+    /// Point this file at `table_store`, re-interning its existing chunks
+    /// if it was chunked against a different store (e.g. its own private
+    /// one from before being added to a filesystem). A no-op if it's
+    /// already sharing `table_store`.
+    pub(crate) fn rebind_chunk_store(
+        &mut self,
+        py: Python<'_>,
+        table_store: &Arc<Mutex<ChunkStore>>,
+    ) {
+        if Arc::ptr_eq(&self.chunk_store, table_store) {
+            return;
+        }
+        let data = self.assemble(py);
+        self.chunk_store.lock().release(&self.chunks);
+        self.chunk_store = Arc::clone(table_store);
+        self.chunks = self.chunk_store.lock().split_and_intern(py, &data);
+    }
+}
+
 /// A directory in the filesystem
 #[pyclass(name = "Directory")]
 pub struct PyDirectory {
@@ -188,6 +330,14 @@ pub struct PyDirectory {
     pub name: String,
     #[pyo3(get, set)]
     pub mode: u16,
+    #[pyo3(get, set)]
+    pub uid: u32,
+    #[pyo3(get, set)]
+    pub gid: u32,
+    /// Extended attributes (xattr), keyed by name; exposed to Python via
+    /// `get_xattr`/`set_xattr`/`list_xattr`/`remove_xattr` and to FUSE via
+    /// `InodeTable::{get,set,list,remove}xattr`.
+    pub(crate) xattrs: HashMap<String, Vec<u8>>,
     pub(crate) ino: Ino,
     pub(crate) parent_ino: Ino,
     pub(crate) children: HashMap<String, Ino>,
@@ -205,6 +355,9 @@ impl PyDirectory {
         Self {
             name,
             mode,
+            uid: 0,
+            gid: 0,
+            xattrs: HashMap::new(),
             ino: 0,
             parent_ino: 0,
             children: HashMap::new(),
@@ -214,6 +367,26 @@ impl PyDirectory {
         }
     }
 
+    /// Get an extended attribute's value, if set
+    fn get_xattr(&self, py: Python<'_>, name: &str) -> Option<Py<PyBytes>> {
+        self.xattrs.get(name).map(|v| PyBytes::new(py, v).into())
+    }
+
+    /// Set an extended attribute's value
+    fn set_xattr(&mut self, name: String, value: Vec<u8>) {
+        self.xattrs.insert(name, value);
+    }
+
+    /// List the names of all extended attributes
+    fn list_xattr(&self) -> Vec<String> {
+        self.xattrs.keys().cloned().collect()
+    }
+
+    /// Remove an extended attribute, returning whether it was present
+    fn remove_xattr(&mut self, name: &str) -> bool {
+        self.xattrs.remove(name).is_some()
+    }
+
     fn __repr__(&self) -> String {
         format!(
             "Directory(name={:?}, children={}, mode={:#o})",
@@ -224,6 +397,49 @@ impl PyDirectory {
     }
 }
 
+/// Configuration for a directory whose children are populated on demand by
+/// a Python callback instead of eagerly, for mounting huge or generated
+/// trees (a database, an archive index, a remote store) without building
+/// every inode up front. Passed to `InodeTable::insert_lazy_dir`, which
+/// materializes it as a normal `Directory` node and remembers the callbacks
+/// on the side so lookups/listings can consult them on demand.
+#[pyclass(name = "LazyDirectory")]
+pub struct PyLazyDirectory {
+    #[pyo3(get)]
+    pub name: String,
+    #[pyo3(get, set)]
+    pub mode: u16,
+    /// `fn(name: str) -> Optional[File | Directory | Symlink]`, consulted
+    /// the first time a not-yet-materialized name is looked up.
+    pub(crate) on_lookup: Option<Py<PyAny>>,
+    /// `fn() -> Iterator[tuple[str, File | Directory | Symlink]]`,
+    /// consulted once to materialize the full listing.
+    pub(crate) on_list: Option<Py<PyAny>>,
+}
+
+#[pymethods]
+impl PyLazyDirectory {
+    #[new]
+    #[pyo3(signature = (name, mode=0o755, on_lookup=None, on_list=None))]
+    pub fn new(
+        name: String,
+        mode: u16,
+        on_lookup: Option<Py<PyAny>>,
+        on_list: Option<Py<PyAny>>,
+    ) -> Self {
+        Self {
+            name,
+            mode,
+            on_lookup,
+            on_list,
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("LazyDirectory(name={:?}, mode={:#o})", self.name, self.mode)
+    }
+}
+
 /// A symbolic link in the filesystem
 #[pyclass(name = "Symlink")]
 pub struct PySymlink {
@@ -231,6 +447,14 @@ pub struct PySymlink {
     pub name: String,
     #[pyo3(get, set)]
     pub target: String,
+    #[pyo3(get, set)]
+    pub uid: u32,
+    #[pyo3(get, set)]
+    pub gid: u32,
+    /// Extended attributes (xattr), keyed by name; exposed to Python via
+    /// `get_xattr`/`set_xattr`/`list_xattr`/`remove_xattr` and to FUSE via
+    /// `InodeTable::{get,set,list,remove}xattr`.
+    pub(crate) xattrs: HashMap<String, Vec<u8>>,
     pub(crate) ino: Ino,
     pub(crate) parent_ino: Ino,
     pub(crate) mtime: SystemTime,
@@ -246,6 +470,9 @@ impl PySymlink {
         Self {
             name,
             target,
+            uid: 0,
+            gid: 0,
+            xattrs: HashMap::new(),
             ino: 0,
             parent_ino: 0,
             mtime: now,
@@ -254,34 +481,282 @@ impl PySymlink {
         }
     }
 
+    /// Get an extended attribute's value, if set
+    fn get_xattr(&self, py: Python<'_>, name: &str) -> Option<Py<PyBytes>> {
+        self.xattrs.get(name).map(|v| PyBytes::new(py, v).into())
+    }
+
+    /// Set an extended attribute's value
+    fn set_xattr(&mut self, name: String, value: Vec<u8>) {
+        self.xattrs.insert(name, value);
+    }
+
+    /// List the names of all extended attributes
+    fn list_xattr(&self) -> Vec<String> {
+        self.xattrs.keys().cloned().collect()
+    }
+
+    /// Remove an extended attribute, returning whether it was present
+    fn remove_xattr(&mut self, name: &str) -> bool {
+        self.xattrs.remove(name).is_some()
+    }
+
     fn __repr__(&self) -> String {
         format!("Symlink(name={:?}, target={:?})", self.name, self.target)
     }
 }
 
+/// A named pipe, socket, or device node in the filesystem
+#[pyclass(name = "Special")]
+pub struct PySpecial {
+    #[pyo3(get)]
+    pub name: String,
+    pub(crate) kind: FileKind,
+    #[pyo3(get, set)]
+    pub mode: u16,
+    #[pyo3(get, set)]
+    pub uid: u32,
+    #[pyo3(get, set)]
+    pub gid: u32,
+    /// Device number (`makedev(major, minor)`); unused for FIFOs and sockets
+    #[pyo3(get)]
+    pub rdev: u32,
+    /// Extended attributes (xattr), keyed by name; exposed to Python via
+    /// `get_xattr`/`set_xattr`/`list_xattr`/`remove_xattr` and to FUSE via
+    /// `InodeTable::{get,set,list,remove}xattr`.
+    pub(crate) xattrs: HashMap<String, Vec<u8>>,
+    pub(crate) ino: Ino,
+    pub(crate) parent_ino: Ino,
+    pub(crate) mtime: SystemTime,
+    pub(crate) atime: SystemTime,
+    pub(crate) ctime: SystemTime,
+}
+
+impl PySpecial {
+    /// Construct a named pipe, socket, or device node (created via FUSE `mknod`)
+    pub(crate) fn new(name: String, kind: FileKind, mode: u16, rdev: u32) -> Self {
+        let now = SystemTime::now();
+        Self {
+            name,
+            kind,
+            mode,
+            uid: 0,
+            gid: 0,
+            rdev,
+            xattrs: HashMap::new(),
+            ino: 0,
+            parent_ino: 0,
+            mtime: now,
+            atime: now,
+            ctime: now,
+        }
+    }
+}
+
+#[pymethods]
+impl PySpecial {
+    /// The node's type as a string: "fifo", "socket", "char_device", or "block_device"
+    #[getter]
+    fn kind(&self) -> &'static str {
+        match self.kind {
+            FileKind::NamedPipe => "fifo",
+            FileKind::Socket => "socket",
+            FileKind::CharDevice => "char_device",
+            FileKind::BlockDevice => "block_device",
+            _ => unreachable!("Special node with non-special kind"),
+        }
+    }
+
+    fn get_xattr(&self, py: Python<'_>, name: &str) -> Option<Py<PyBytes>> {
+        self.xattrs.get(name).map(|v| PyBytes::new(py, v).into())
+    }
+
+    fn set_xattr(&mut self, name: String, value: Vec<u8>) {
+        self.xattrs.insert(name, value);
+    }
+
+    fn list_xattr(&self) -> Vec<String> {
+        self.xattrs.keys().cloned().collect()
+    }
+
+    fn remove_xattr(&mut self, name: &str) -> bool {
+        self.xattrs.remove(name).is_some()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Special(name={:?}, kind={:?}, rdev={})",
+            self.name, self.kind, self.rdev
+        )
+    }
+}
+
 /// The in-memory inode table
 pub struct InodeTable {
     inodes: HashMap<Ino, NodeRef>,
     next_ino: Ino,
+    /// Number of directory entries (dentries) referencing each inode;
+    /// the node is only dropped from `inodes` once this reaches zero.
+    link_counts: HashMap<Ino, u32>,
+    /// Per-inode FUSE lookup count: incremented once for every `lookup`,
+    /// `create`, `mkdir`, `mknod`, `symlink`, or `link` reply that hands the
+    /// kernel a reference to the inode, decremented by `forget`'s `nlookup`.
+    lookup_counts: HashMap<Ino, u64>,
+    /// Inodes unlinked from every directory entry (link count reached zero)
+    /// but still kept alive because the kernel hasn't `forget`-ten them yet.
+    unlinked: HashSet<Ino>,
+    /// Lazy-population state for directories created via
+    /// [`InodeTable::insert_lazy_dir`], keyed by the directory's inode.
+    lazy: HashMap<Ino, LazyState>,
+    /// Dedup store for file content chunks, shared by every `PyFile` in
+    /// this table; see `crate::chunk`.
+    chunk_store: Arc<Mutex<ChunkStore>>,
+    /// When set, every mutating entry point (`insert_file`, `insert_dir`,
+    /// `insert_symlink`, `remove`, `rename`, and `PyFile::write`/`truncate`)
+    /// fails with [`read_only_error`] instead of applying the change.
+    /// Shared with every `PyFile` already in the table (see
+    /// `PyFile::read_only`) so toggling it via [`InodeTable::set_read_only`]
+    /// takes effect immediately, even for files inserted before the toggle.
+    read_only: Arc<AtomicBool>,
     pub uid: u32,
     pub gid: u32,
 }
 
+/// Per-inode bookkeeping for a [`PyLazyDirectory`]-backed directory: the
+/// callbacks to consult, and whether `on_list` has already run so a full
+/// listing doesn't re-invoke it.
+struct LazyState {
+    on_lookup: Option<Py<PyAny>>,
+    on_list: Option<Py<PyAny>>,
+    listed: bool,
+}
+
 impl InodeTable {
-    pub fn new(uid: u32, gid: u32) -> Self {
+    pub fn new(uid: u32, gid: u32, read_only: bool) -> Self {
         Self {
             inodes: HashMap::new(),
             next_ino: ROOT_INO + 1,
+            link_counts: HashMap::new(),
+            lookup_counts: HashMap::new(),
+            unlinked: HashSet::new(),
+            lazy: HashMap::new(),
+            chunk_store: Arc::new(Mutex::new(ChunkStore::new())),
+            read_only: Arc::new(AtomicBool::new(read_only)),
             uid,
             gid,
         }
     }
 
+    /// Whether mutating entry points currently reject changes; see
+    /// [`InodeTable::read_only`].
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::Relaxed)
+    }
+
+    /// Flip the read-only flag. Takes effect immediately for every file
+    /// already in the table, not just ones inserted afterward.
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only.store(read_only, Ordering::Relaxed);
+    }
+
+    /// Number of directory entries referencing `ino`
+    pub fn nlink(&self, ino: Ino) -> u32 {
+        self.link_counts.get(&ino).copied().unwrap_or(1)
+    }
+
+    /// Record that the kernel now holds a reference to `ino`, in response to
+    /// a `lookup`/`create`/`mkdir`/`mknod`/`symlink`/`link` reply.
+    pub fn note_lookup(&mut self, ino: Ino) {
+        *self.lookup_counts.entry(ino).or_insert(0) += 1;
+    }
+
+    /// Handle a FUSE `forget`: drop `nlookup` references to `ino`. An inode
+    /// that was unlinked while still referenced is only actually freed once
+    /// its lookup count reaches zero here.
+    pub fn forget(&mut self, py: Python<'_>, ino: Ino, nlookup: u64) {
+        let Some(count) = self.lookup_counts.get_mut(&ino) else {
+            return;
+        };
+        *count = count.saturating_sub(nlookup);
+        if *count == 0 {
+            self.lookup_counts.remove(&ino);
+            if self.unlinked.remove(&ino) {
+                // Dropping the node here (inside the GIL held by `py`)
+                // correctly releases any `Py<T>` references it holds.
+                if let Some(node) = self.inodes.remove(&ino) {
+                    self.release_chunks_if_file(py, &node);
+                }
+            }
+        }
+    }
+
+    /// Release a file's chunk references from the shared chunk store; a
+    /// no-op for non-file nodes. Must be called whenever a file node is
+    /// actually dropped from `inodes`, since the chunk store holds its own
+    /// `Py<PyBytes>` references independent of the node's lifetime.
+    fn release_chunks_if_file(&self, py: Python<'_>, node: &NodeRef) {
+        if let NodeRef::File(f) = node {
+            self.chunk_store.lock().release(&f.borrow(py).chunks);
+        }
+    }
+
+    /// Create an additional directory entry pointing at an existing file
+    /// inode (`link(2)`). Directories cannot be hard-linked.
+    pub fn link(
+        &mut self,
+        py: Python<'_>,
+        ino: Ino,
+        new_parent: Ino,
+        new_name: &str,
+    ) -> PyResult<()> {
+        match self.inodes.get(&ino) {
+            Some(NodeRef::Dir(_)) => {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                    "Cannot hard-link a directory",
+                ));
+            }
+            Some(_) => {}
+            None => {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                    "Source not found",
+                ));
+            }
+        }
+
+        match self.inodes.get(&new_parent) {
+            Some(NodeRef::Dir(parent)) => {
+                let mut p = parent.borrow_mut(py);
+                p.children.insert(new_name.to_string(), ino);
+                p.mtime = SystemTime::now();
+                p.ctime = SystemTime::now();
+            }
+            _ => {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                    "Parent is not a directory",
+                ));
+            }
+        }
+
+        *self.link_counts.entry(ino).or_insert(1) += 1;
+
+        let now = SystemTime::now();
+        match self.inodes.get(&ino) {
+            Some(NodeRef::File(f)) => f.borrow_mut(py).ctime = now,
+            Some(NodeRef::Symlink(s)) => s.borrow_mut(py).ctime = now,
+            Some(NodeRef::Special(s)) => s.borrow_mut(py).ctime = now,
+            _ => {}
+        }
+
+        Ok(())
+    }
+
     /// Initialize with a root directory
     pub fn init_root(&mut self, py: Python<'_>) -> PyResult<Py<PyDirectory>> {
         let mut root = PyDirectory::new(String::new(), 0o755);
         root.ino = ROOT_INO;
         root.parent_ino = ROOT_INO; // Root is its own parent
+        root.uid = self.uid;
+        root.gid = self.gid;
         let root_py = Py::new(py, root)?;
         self.inodes
             .insert(ROOT_INO, NodeRef::Dir(root_py.clone_ref(py)));
@@ -319,6 +794,43 @@ impl InodeTable {
         }
     }
 
+    pub fn get_special(&self, ino: Ino) -> Option<&Py<PySpecial>> {
+        match self.inodes.get(&ino)? {
+            NodeRef::Special(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Insert a named pipe, socket, or device node into a directory
+    pub fn insert_special(
+        &mut self,
+        py: Python<'_>,
+        parent_ino: Ino,
+        special: Py<PySpecial>,
+    ) -> PyResult<Ino> {
+        let ino = self.alloc_ino();
+
+        {
+            let mut s = special.borrow_mut(py);
+            s.ino = ino;
+            s.parent_ino = parent_ino;
+            s.uid = self.uid;
+            s.gid = self.gid;
+        }
+
+        let name = special.borrow(py).name.clone();
+
+        if let Some(NodeRef::Dir(parent)) = self.inodes.get(&parent_ino) {
+            let mut p = parent.borrow_mut(py);
+            p.children.insert(name, ino);
+            p.mtime = SystemTime::now();
+            p.ctime = SystemTime::now();
+        }
+
+        self.inodes.insert(ino, NodeRef::Special(special));
+        Ok(ino)
+    }
+
     /// Insert a file into a directory
     pub fn insert_file(
         &mut self,
@@ -326,6 +838,9 @@ impl InodeTable {
         parent_ino: Ino,
         file: Py<PyFile>,
     ) -> PyResult<Ino> {
+        if self.is_read_only() {
+            return Err(read_only_error());
+        }
         let ino = self.alloc_ino();
 
         // Update file's inode info
@@ -333,6 +848,10 @@ impl InodeTable {
             let mut f = file.borrow_mut(py);
             f.ino = ino;
             f.parent_ino = parent_ino;
+            f.uid = self.uid;
+            f.gid = self.gid;
+            f.rebind_chunk_store(py, &self.chunk_store);
+            f.read_only = Arc::clone(&self.read_only);
         }
 
         let name = file.borrow(py).name.clone();
@@ -356,6 +875,9 @@ impl InodeTable {
         parent_ino: Ino,
         dir: Py<PyDirectory>,
     ) -> PyResult<Ino> {
+        if self.is_read_only() {
+            return Err(read_only_error());
+        }
         let ino = self.alloc_ino();
 
         // Update dir's inode info
@@ -363,6 +885,8 @@ impl InodeTable {
             let mut d = dir.borrow_mut(py);
             d.ino = ino;
             d.parent_ino = parent_ino;
+            d.uid = self.uid;
+            d.gid = self.gid;
         }
 
         let name = dir.borrow(py).name.clone();
@@ -386,6 +910,9 @@ impl InodeTable {
         parent_ino: Ino,
         symlink: Py<PySymlink>,
     ) -> PyResult<Ino> {
+        if self.is_read_only() {
+            return Err(read_only_error());
+        }
         let ino = self.alloc_ino();
 
         // Update symlink's inode info
@@ -393,6 +920,8 @@ impl InodeTable {
             let mut s = symlink.borrow_mut(py);
             s.ino = ino;
             s.parent_ino = parent_ino;
+            s.uid = self.uid;
+            s.gid = self.gid;
         }
 
         let name = symlink.borrow(py).name.clone();
@@ -409,36 +938,221 @@ impl InodeTable {
         Ok(ino)
     }
 
-    /// Remove a node from the filesystem
-    pub fn remove(&mut self, py: Python<'_>, ino: Ino) -> PyResult<Option<NodeRef>> {
-        if let Some(node) = self.inodes.remove(&ino) {
-            // Get parent and name from the node
-            let (parent_ino, name) = match &node {
-                NodeRef::File(f) => {
-                    let f = f.borrow(py);
-                    (f.parent_ino, f.name.clone())
-                }
-                NodeRef::Dir(d) => {
-                    let d = d.borrow(py);
-                    (d.parent_ino, d.name.clone())
-                }
-                NodeRef::Symlink(s) => {
-                    let s = s.borrow(py);
-                    (s.parent_ino, s.name.clone())
-                }
-            };
+    /// Create a directory whose children are populated on demand by
+    /// `lazy`'s callbacks rather than eagerly. The returned inode is a
+    /// normal `Directory` node; [`InodeTable::ensure_lazy_entry_unlocked`] and
+    /// [`InodeTable::ensure_lazy_listed_unlocked`] consult the callbacks and
+    /// materialize results into its `children` map via the usual
+    /// `insert_file`/`insert_dir`/`insert_symlink` paths.
+    pub fn insert_lazy_dir(
+        &mut self,
+        py: Python<'_>,
+        parent_ino: Ino,
+        lazy: Py<PyLazyDirectory>,
+    ) -> PyResult<Ino> {
+        let (name, mode, on_lookup, on_list) = {
+            let l = lazy.borrow(py);
+            (l.name.clone(), l.mode, l.on_lookup.clone_ref(py), l.on_list.clone_ref(py))
+        };
+        let dir_py = Py::new(py, PyDirectory::new(name, mode))?;
+        let ino = self.insert_dir(py, parent_ino, dir_py)?;
+        self.lazy.insert(
+            ino,
+            LazyState {
+                on_lookup,
+                on_list,
+                listed: false,
+            },
+        );
+        Ok(ino)
+    }
 
-            // Remove from parent's children
-            if let Some(NodeRef::Dir(parent)) = self.inodes.get(&parent_ino) {
-                let mut p = parent.borrow_mut(py);
-                p.children.remove(&name);
-                p.mtime = SystemTime::now();
-                p.ctime = SystemTime::now();
+    /// Mark a lazy directory's cached listing/entries as stale: the next
+    /// lookup or listing touching it re-consults its callbacks. The
+    /// directory's `children` map is cleared right away; already
+    /// materialized child inodes stay in the table but are no longer
+    /// reachable by name, and re-fetched entries get fresh inodes. Returns
+    /// `false` if `ino` isn't a lazy directory.
+    pub fn invalidate_lazy_dir(&mut self, py: Python<'_>, ino: Ino) -> bool {
+        let Some(state) = self.lazy.get_mut(&ino) else {
+            return false;
+        };
+        state.listed = false;
+        if let Some(NodeRef::Dir(d)) = self.inodes.get(&ino) {
+            d.borrow_mut(py).children.clear();
+        }
+        true
+    }
+
+    /// Whether `parent_ino` is a lazy directory with an unmaterialized
+    /// `on_lookup` callback still pending for `name`.
+    /// [`InodeTable::ensure_lazy_entry_unlocked`] calls this under one short
+    /// lock and returns early when there's no callback to invoke.
+    fn lazy_lookup_callback(&self, py: Python<'_>, parent_ino: Ino, name: &str) -> Option<Py<PyAny>> {
+        if self.lookup(py, parent_ino, name).is_some() {
+            return None;
+        }
+        self.lazy
+            .get(&parent_ino)
+            .and_then(|s| s.on_lookup.as_ref().map(|c| c.clone_ref(py)))
+    }
+
+    /// Whether `ino` is a lazy directory whose listing hasn't been fetched
+    /// yet, and if so, its `on_list` callback (or `None` if the directory
+    /// has no listing callback, in which case it's marked listed directly).
+    fn lazy_list_callback(&mut self, py: Python<'_>, ino: Ino) -> Option<Py<PyAny>> {
+        let state = self.lazy.get(&ino)?;
+        if state.listed {
+            return None;
+        }
+        match state.on_list.as_ref().map(|c| c.clone_ref(py)) {
+            Some(callback) => Some(callback),
+            None => {
+                self.lazy.get_mut(&ino).unwrap().listed = true;
+                None
+            }
+        }
+    }
+
+    /// Insert whatever a lazy-directory callback returned (a `File`,
+    /// `Directory`, or `Symlink`) as a child named `name` of `parent_ino`.
+    fn materialize_lazy_child(
+        &mut self,
+        py: Python<'_>,
+        parent_ino: Ino,
+        name: &str,
+        obj: &Bound<'_, PyAny>,
+    ) -> PyResult<Ino> {
+        if let Ok(file_py) = obj.extract::<Py<PyFile>>() {
+            file_py.borrow_mut(py).name = name.to_string();
+            return self.insert_file(py, parent_ino, file_py);
+        }
+        if let Ok(dir_py) = obj.extract::<Py<PyDirectory>>() {
+            dir_py.borrow_mut(py).name = name.to_string();
+            return self.insert_dir(py, parent_ino, dir_py);
+        }
+        if let Ok(sym_py) = obj.extract::<Py<PySymlink>>() {
+            sym_py.borrow_mut(py).name = name.to_string();
+            return self.insert_symlink(py, parent_ino, sym_py);
+        }
+        Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+            "LazyDirectory callback for {:?} must return a File, Directory, or Symlink",
+            name
+        )))
+    }
+
+    /// If `parent_ino` is a lazy directory and `name` hasn't been
+    /// materialized yet, consult its `on_lookup` callback and insert
+    /// whatever it returns. A no-op for non-lazy directories or names
+    /// already present.
+    ///
+    /// Takes `inodes` rather than `&mut self` because `on_lookup` runs
+    /// arbitrary Python, which may call back into the filesystem; `inodes`'s
+    /// lock is released for the duration of that call and only held for the
+    /// bookkeeping before and after, so a callback that calls back into the
+    /// filesystem can take the (non-reentrant) lock itself instead of
+    /// deadlocking. Callers (FUSE `lookup`, `resolve_path`, ...) must not
+    /// hold the lock when calling this.
+    pub fn ensure_lazy_entry_unlocked(
+        inodes: &Arc<Mutex<InodeTable>>,
+        py: Python<'_>,
+        parent_ino: Ino,
+        name: &str,
+    ) -> PyResult<()> {
+        let Some(callback) = inodes.lock().lazy_lookup_callback(py, parent_ino, name) else {
+            return Ok(());
+        };
+        let result = callback.call1(py, (name,))?;
+        if result.is_none(py) {
+            return Ok(());
+        }
+        let mut table = inodes.lock();
+        // Re-check: another thread may have materialized (or the directory
+        // may have been invalidated/re-listed) while the lock was released.
+        if table.lookup(py, parent_ino, name).is_some() {
+            return Ok(());
+        }
+        table.materialize_lazy_child(py, parent_ino, name, result.bind(py))?;
+        Ok(())
+    }
+
+    /// If `ino` is a lazy directory whose full listing hasn't been fetched
+    /// yet, consult its `on_list` callback and materialize every entry it
+    /// yields. A no-op for non-lazy directories or ones already listed.
+    ///
+    /// Same rationale and locking discipline as
+    /// [`InodeTable::ensure_lazy_entry_unlocked`].
+    pub fn ensure_lazy_listed_unlocked(
+        inodes: &Arc<Mutex<InodeTable>>,
+        py: Python<'_>,
+        ino: Ino,
+    ) -> PyResult<()> {
+        let Some(callback) = inodes.lock().lazy_list_callback(py, ino) else {
+            return Ok(());
+        };
+        let entries: Vec<(String, Py<PyAny>)> = callback
+            .call0(py)?
+            .bind(py)
+            .try_iter()?
+            .map(|item| -> PyResult<(String, Py<PyAny>)> { Ok(item?.extract()?) })
+            .collect::<PyResult<_>>()?;
+        let mut table = inodes.lock();
+        for (name, node) in entries {
+            if table.lookup(py, ino, &name).is_some() {
+                continue;
             }
+            table.materialize_lazy_child(py, ino, &name, node.bind(py))?;
+        }
+        if let Some(state) = table.lazy.get_mut(&ino) {
+            state.listed = true;
+        }
+        Ok(())
+    }
+
+    /// Remove the dentry `name` from directory `parent_ino` (`unlink(2)`/`rmdir(2)`).
+    /// The underlying node is only dropped once its link count reaches zero,
+    /// so data behind other hard links survives the removal of this one
+    /// name. A node whose last name is removed while the kernel still holds
+    /// a lookup reference to it stays resident until `forget` drops that.
+    pub fn remove(&mut self, py: Python<'_>, parent_ino: Ino, name: &str) -> PyResult<Option<NodeRef>> {
+        if self.is_read_only() {
+            return Err(read_only_error());
+        }
+        let ino = match self.lookup(py, parent_ino, name) {
+            Some(ino) => ino,
+            None => return Ok(None),
+        };
 
-            Ok(Some(node))
+        // Remove this specific dentry from its parent.
+        if let Some(NodeRef::Dir(parent)) = self.inodes.get(&parent_ino) {
+            let mut p = parent.borrow_mut(py);
+            p.children.remove(name);
+            p.mtime = SystemTime::now();
+            p.ctime = SystemTime::now();
+        }
+
+        let remaining = {
+            let count = self.link_counts.entry(ino).or_insert(1);
+            *count = count.saturating_sub(1);
+            *count
+        };
+
+        if remaining == 0 {
+            self.link_counts.remove(&ino);
+            if self.lookup_counts.get(&ino).copied().unwrap_or(0) > 0 {
+                // Still referenced by an outstanding FUSE lookup; keep the
+                // node resident until `forget` releases the last one.
+                self.unlinked.insert(ino);
+                Ok(self.inodes.get(&ino).map(|n| n.clone_ref(py)))
+            } else {
+                let node = self.inodes.remove(&ino);
+                if let Some(n) = &node {
+                    self.release_chunks_if_file(py, n);
+                }
+                Ok(node)
+            }
         } else {
-            Ok(None)
+            Ok(self.inodes.get(&ino).map(|n| n.clone_ref(py)))
         }
     }
 
@@ -450,13 +1164,72 @@ impl InodeTable {
         }
     }
 
+    /// Get a single extended attribute's value for an inode
+    pub fn getxattr(&self, py: Python<'_>, ino: Ino, name: &str) -> Option<Vec<u8>> {
+        match self.inodes.get(&ino)? {
+            NodeRef::File(f) => f.borrow(py).xattrs.get(name).cloned(),
+            NodeRef::Dir(d) => d.borrow(py).xattrs.get(name).cloned(),
+            NodeRef::Symlink(s) => s.borrow(py).xattrs.get(name).cloned(),
+            NodeRef::Special(s) => s.borrow(py).xattrs.get(name).cloned(),
+        }
+    }
+
+    /// Set an extended attribute on an inode
+    pub fn setxattr(&self, py: Python<'_>, ino: Ino, name: &str, value: &[u8]) -> bool {
+        match self.inodes.get(&ino) {
+            Some(NodeRef::File(f)) => {
+                f.borrow_mut(py).xattrs.insert(name.to_string(), value.to_vec());
+                true
+            }
+            Some(NodeRef::Dir(d)) => {
+                d.borrow_mut(py).xattrs.insert(name.to_string(), value.to_vec());
+                true
+            }
+            Some(NodeRef::Symlink(s)) => {
+                s.borrow_mut(py).xattrs.insert(name.to_string(), value.to_vec());
+                true
+            }
+            Some(NodeRef::Special(s)) => {
+                s.borrow_mut(py).xattrs.insert(name.to_string(), value.to_vec());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// List the names of all extended attributes on an inode, NUL-separated
+    pub fn listxattr(&self, py: Python<'_>, ino: Ino) -> Option<Vec<u8>> {
+        let names: Vec<String> = match self.inodes.get(&ino)? {
+            NodeRef::File(f) => f.borrow(py).xattrs.keys().cloned().collect(),
+            NodeRef::Dir(d) => d.borrow(py).xattrs.keys().cloned().collect(),
+            NodeRef::Symlink(s) => s.borrow(py).xattrs.keys().cloned().collect(),
+            NodeRef::Special(s) => s.borrow(py).xattrs.keys().cloned().collect(),
+        };
+        let mut buf = Vec::new();
+        for name in names {
+            buf.extend_from_slice(name.as_bytes());
+            buf.push(0);
+        }
+        Some(buf)
+    }
+
+    /// Remove an extended attribute from an inode, returning whether it was present
+    pub fn removexattr(&self, py: Python<'_>, ino: Ino, name: &str) -> Option<bool> {
+        match self.inodes.get(&ino)? {
+            NodeRef::File(f) => Some(f.borrow_mut(py).xattrs.remove(name).is_some()),
+            NodeRef::Dir(d) => Some(d.borrow_mut(py).xattrs.remove(name).is_some()),
+            NodeRef::Symlink(s) => Some(s.borrow_mut(py).xattrs.remove(name).is_some()),
+            NodeRef::Special(s) => Some(s.borrow_mut(py).xattrs.remove(name).is_some()),
+        }
+    }
+
     /// Get file attributes for an inode
     pub fn getattr(&self, py: Python<'_>, ino: Ino) -> Option<FileAttr> {
         let node = self.inodes.get(&ino)?;
         match node {
             NodeRef::File(f) => {
                 let f = f.borrow(py);
-                let size = f.content.bind(py).as_bytes().len() as u64;
+                let size = f.len(py) as u64;
                 Some(FileAttr {
                     ino,
                     size,
@@ -467,9 +1240,10 @@ impl InodeTable {
                     crtime: f.ctime,
                     kind: FileKind::File,
                     perm: f.mode,
-                    nlink: 1,
-                    uid: self.uid,
-                    gid: self.gid,
+                    nlink: self.nlink(ino),
+                    uid: f.uid,
+                    gid: f.gid,
+                    rdev: 0,
                 })
             }
             NodeRef::Dir(d) => {
@@ -485,8 +1259,9 @@ impl InodeTable {
                     kind: FileKind::Directory,
                     perm: d.mode,
                     nlink: 2 + d.children.len() as u32,
-                    uid: self.uid,
-                    gid: self.gid,
+                    uid: d.uid,
+                    gid: d.gid,
+                    rdev: 0,
                 })
             }
             NodeRef::Symlink(s) => {
@@ -502,9 +1277,28 @@ impl InodeTable {
                     crtime: s.ctime,
                     kind: FileKind::Symlink,
                     perm: 0o777, // Symlinks are always 777
-                    nlink: 1,
-                    uid: self.uid,
-                    gid: self.gid,
+                    nlink: self.nlink(ino),
+                    uid: s.uid,
+                    gid: s.gid,
+                    rdev: 0,
+                })
+            }
+            NodeRef::Special(s) => {
+                let s = s.borrow(py);
+                Some(FileAttr {
+                    ino,
+                    size: 0,
+                    blocks: 0,
+                    atime: s.atime,
+                    mtime: s.mtime,
+                    ctime: s.ctime,
+                    crtime: s.ctime,
+                    kind: s.kind,
+                    perm: s.mode,
+                    nlink: self.nlink(ino),
+                    uid: s.uid,
+                    gid: s.gid,
+                    rdev: s.rdev,
                 })
             }
         }
@@ -519,6 +1313,9 @@ impl InodeTable {
         new_parent: Ino,
         new_name: &str,
     ) -> PyResult<()> {
+        if self.is_read_only() {
+            return Err(read_only_error());
+        }
         // Get the inode being moved
         let ino = self
             .lookup(py, old_parent, old_name)
@@ -532,12 +1329,25 @@ impl InodeTable {
             p.ctime = SystemTime::now();
         }
 
-        // Update the node's name and parent
+        // Every node still carries its own `name`/`parent_ino`, which has
+        // room for exactly one dentry's worth of bookkeeping (the directory
+        // case below never has a choice: a directory can't be hard-linked,
+        // so it only ever has the one dentry these fields already track).
+        // A hard-linked file/symlink/special can have several live
+        // dentries sharing one inode, and these fields can only agree with
+        // one of them at a time. Rather than using `nlink()` as a proxy for
+        // "is this the dentry the fields track" -- wrong the moment a node
+        // has ever had more than one link, even after it's back down to one
+        // -- check directly whether the fields still match the dentry this
+        // call is actually renaming, and only update them in that case.
+        // Renaming any of the node's *other* links leaves them alone.
         match self.inodes.get(&ino) {
             Some(NodeRef::File(f)) => {
                 let mut file = f.borrow_mut(py);
-                file.name = new_name.to_string();
-                file.parent_ino = new_parent;
+                if file.parent_ino == old_parent && file.name == old_name {
+                    file.name = new_name.to_string();
+                    file.parent_ino = new_parent;
+                }
                 file.ctime = SystemTime::now();
             }
             Some(NodeRef::Dir(d)) => {
@@ -548,10 +1358,20 @@ impl InodeTable {
             }
             Some(NodeRef::Symlink(s)) => {
                 let mut sym = s.borrow_mut(py);
-                sym.name = new_name.to_string();
-                sym.parent_ino = new_parent;
+                if sym.parent_ino == old_parent && sym.name == old_name {
+                    sym.name = new_name.to_string();
+                    sym.parent_ino = new_parent;
+                }
                 sym.ctime = SystemTime::now();
             }
+            Some(NodeRef::Special(s)) => {
+                let mut special = s.borrow_mut(py);
+                if special.parent_ino == old_parent && special.name == old_name {
+                    special.name = new_name.to_string();
+                    special.parent_ino = new_parent;
+                }
+                special.ctime = SystemTime::now();
+            }
             None => {
                 return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
                     "Node not found",
@@ -569,4 +1389,395 @@ impl InodeTable {
 
         Ok(())
     }
+
+    /// Atomically swap two existing dentries (`RENAME_EXCHANGE`), leaving
+    /// both inodes in place but pointed at by each other's former name.
+    pub fn exchange(
+        &mut self,
+        py: Python<'_>,
+        parent_a: Ino,
+        name_a: &str,
+        parent_b: Ino,
+        name_b: &str,
+    ) -> PyResult<()> {
+        let ino_a = self
+            .lookup(py, parent_a, name_a)
+            .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("Source not found"))?;
+        let ino_b = self.lookup(py, parent_b, name_b).ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>("Destination not found")
+        })?;
+
+        let now = SystemTime::now();
+
+        if let Some(NodeRef::Dir(parent)) = self.inodes.get(&parent_a) {
+            let mut p = parent.borrow_mut(py);
+            p.children.insert(name_a.to_string(), ino_b);
+            p.mtime = now;
+            p.ctime = now;
+        }
+        if let Some(NodeRef::Dir(parent)) = self.inodes.get(&parent_b) {
+            let mut p = parent.borrow_mut(py);
+            p.children.insert(name_b.to_string(), ino_a);
+            p.mtime = now;
+            p.ctime = now;
+        }
+
+        match self.inodes.get(&ino_a) {
+            Some(NodeRef::File(f)) => {
+                let mut n = f.borrow_mut(py);
+                n.name = name_b.to_string();
+                n.parent_ino = parent_b;
+                n.ctime = now;
+            }
+            Some(NodeRef::Dir(d)) => {
+                let mut n = d.borrow_mut(py);
+                n.name = name_b.to_string();
+                n.parent_ino = parent_b;
+                n.ctime = now;
+            }
+            Some(NodeRef::Symlink(s)) => {
+                let mut n = s.borrow_mut(py);
+                n.name = name_b.to_string();
+                n.parent_ino = parent_b;
+                n.ctime = now;
+            }
+            Some(NodeRef::Special(s)) => {
+                let mut n = s.borrow_mut(py);
+                n.name = name_b.to_string();
+                n.parent_ino = parent_b;
+                n.ctime = now;
+            }
+            None => return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("Source not found")),
+        }
+
+        match self.inodes.get(&ino_b) {
+            Some(NodeRef::File(f)) => {
+                let mut n = f.borrow_mut(py);
+                n.name = name_a.to_string();
+                n.parent_ino = parent_a;
+                n.ctime = now;
+            }
+            Some(NodeRef::Dir(d)) => {
+                let mut n = d.borrow_mut(py);
+                n.name = name_a.to_string();
+                n.parent_ino = parent_a;
+                n.ctime = now;
+            }
+            Some(NodeRef::Symlink(s)) => {
+                let mut n = s.borrow_mut(py);
+                n.name = name_a.to_string();
+                n.parent_ino = parent_a;
+                n.ctime = now;
+            }
+            Some(NodeRef::Special(s)) => {
+                let mut n = s.borrow_mut(py);
+                n.name = name_a.to_string();
+                n.parent_ino = parent_a;
+                n.ctime = now;
+            }
+            None => {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                    "Destination not found",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Build a serializable snapshot of the whole tree, for `MemFS.save`.
+    pub fn to_snapshot(&self, py: Python<'_>) -> Snapshot {
+        let mut nodes = HashMap::with_capacity(self.inodes.len());
+        for (&ino, node) in &self.inodes {
+            let snapshot_node = match node {
+                NodeRef::File(f) => {
+                    let f = f.borrow(py);
+                    SnapshotNode::File(SnapshotFile {
+                        name: f.name.clone(),
+                        content: f.assemble(py),
+                        mode: f.mode,
+                        uid: f.uid,
+                        gid: f.gid,
+                        xattrs: f.xattrs.clone(),
+                        parent_ino: f.parent_ino,
+                        atime: f.atime,
+                        mtime: f.mtime,
+                        ctime: f.ctime,
+                    })
+                }
+                NodeRef::Dir(d) => {
+                    let d = d.borrow(py);
+                    SnapshotNode::Dir(SnapshotDir {
+                        name: d.name.clone(),
+                        mode: d.mode,
+                        uid: d.uid,
+                        gid: d.gid,
+                        xattrs: d.xattrs.clone(),
+                        parent_ino: d.parent_ino,
+                        children: d.children.clone(),
+                        atime: d.atime,
+                        mtime: d.mtime,
+                        ctime: d.ctime,
+                    })
+                }
+                NodeRef::Symlink(s) => {
+                    let s = s.borrow(py);
+                    SnapshotNode::Symlink(SnapshotSymlink {
+                        name: s.name.clone(),
+                        target: s.target.clone(),
+                        uid: s.uid,
+                        gid: s.gid,
+                        xattrs: s.xattrs.clone(),
+                        parent_ino: s.parent_ino,
+                        atime: s.atime,
+                        mtime: s.mtime,
+                        ctime: s.ctime,
+                    })
+                }
+                NodeRef::Special(s) => {
+                    let s = s.borrow(py);
+                    SnapshotNode::Special(SnapshotSpecial {
+                        name: s.name.clone(),
+                        kind: s.kind.into(),
+                        mode: s.mode,
+                        uid: s.uid,
+                        gid: s.gid,
+                        rdev: s.rdev,
+                        xattrs: s.xattrs.clone(),
+                        parent_ino: s.parent_ino,
+                        atime: s.atime,
+                        mtime: s.mtime,
+                        ctime: s.ctime,
+                    })
+                }
+            };
+            nodes.insert(ino, snapshot_node);
+        }
+
+        Snapshot {
+            version: crate::snapshot::SNAPSHOT_VERSION,
+            root_ino: ROOT_INO,
+            next_ino: self.next_ino,
+            uid: self.uid,
+            gid: self.gid,
+            link_counts: self.link_counts.clone(),
+            nodes,
+        }
+    }
+
+    /// Reconstruct an `InodeTable` and its root directory from a snapshot
+    /// produced by [`InodeTable::to_snapshot`].
+    pub fn from_snapshot(py: Python<'_>, snapshot: Snapshot) -> PyResult<(Self, Py<PyDirectory>)> {
+        if snapshot.version != crate::snapshot::SNAPSHOT_VERSION {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "Unsupported snapshot version: {}",
+                snapshot.version
+            )));
+        }
+
+        let chunk_store = Arc::new(Mutex::new(ChunkStore::new()));
+        // A freshly loaded tree is mutable by default; callers wanting an
+        // immutable view call `InodeTable::set_read_only` afterward.
+        let read_only = Arc::new(AtomicBool::new(false));
+        let mut inodes = HashMap::with_capacity(snapshot.nodes.len());
+        for (ino, node) in snapshot.nodes {
+            let node_ref = match node {
+                SnapshotNode::File(f) => {
+                    let chunks = chunk_store.lock().split_and_intern(py, &f.content);
+                    let file = PyFile {
+                        name: f.name,
+                        chunks,
+                        chunk_store: Arc::clone(&chunk_store),
+                        read_only: Arc::clone(&read_only),
+                        mode: f.mode,
+                        uid: f.uid,
+                        gid: f.gid,
+                        xattrs: f.xattrs,
+                        ino,
+                        parent_ino: f.parent_ino,
+                        atime: f.atime,
+                        mtime: f.mtime,
+                        ctime: f.ctime,
+                    };
+                    NodeRef::File(Py::new(py, file)?)
+                }
+                SnapshotNode::Dir(d) => {
+                    let dir = PyDirectory {
+                        name: d.name,
+                        mode: d.mode,
+                        uid: d.uid,
+                        gid: d.gid,
+                        xattrs: d.xattrs,
+                        ino,
+                        parent_ino: d.parent_ino,
+                        children: d.children,
+                        atime: d.atime,
+                        mtime: d.mtime,
+                        ctime: d.ctime,
+                    };
+                    NodeRef::Dir(Py::new(py, dir)?)
+                }
+                SnapshotNode::Symlink(s) => {
+                    let symlink = PySymlink {
+                        name: s.name,
+                        target: s.target,
+                        uid: s.uid,
+                        gid: s.gid,
+                        xattrs: s.xattrs,
+                        ino,
+                        parent_ino: s.parent_ino,
+                        atime: s.atime,
+                        mtime: s.mtime,
+                        ctime: s.ctime,
+                    };
+                    NodeRef::Symlink(Py::new(py, symlink)?)
+                }
+                SnapshotNode::Special(s) => {
+                    let special = PySpecial {
+                        name: s.name,
+                        kind: s.kind.into(),
+                        mode: s.mode,
+                        uid: s.uid,
+                        gid: s.gid,
+                        rdev: s.rdev,
+                        xattrs: s.xattrs,
+                        ino,
+                        parent_ino: s.parent_ino,
+                        atime: s.atime,
+                        mtime: s.mtime,
+                        ctime: s.ctime,
+                    };
+                    NodeRef::Special(Py::new(py, special)?)
+                }
+            };
+            inodes.insert(ino, node_ref);
+        }
+
+        let root = match inodes.get(&snapshot.root_ino) {
+            Some(NodeRef::Dir(d)) => d.clone_ref(py),
+            _ => {
+                return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                    "Snapshot root inode is missing or not a directory",
+                ));
+            }
+        };
+
+        Ok((
+            Self {
+                inodes,
+                next_ino: snapshot.next_ino,
+                link_counts: snapshot.link_counts,
+                // A freshly loaded tree has no outstanding FUSE lookups yet,
+                // and lazy-directory callbacks aren't persisted (Python
+                // callables can't be serialized), so none are restored.
+                lookup_counts: HashMap::new(),
+                unlinked: HashSet::new(),
+                lazy: HashMap::new(),
+                chunk_store,
+                read_only,
+                uid: snapshot.uid,
+                gid: snapshot.gid,
+            },
+            root,
+        ))
+    }
+
+    /// Write the tree to `path` in the append-only docket format (see
+    /// [`crate::docket`]), for warm-starting a mount without rebuilding it
+    /// from Python. If `path` already holds a docket from a previous
+    /// `dump`, only nodes that changed since then are serialized; the rest
+    /// are carried forward verbatim from the existing file, which is read
+    /// back only to find what changed.
+    pub fn dump(&self, py: Python<'_>, path: &std::path::Path) -> std::io::Result<()> {
+        let previous = std::fs::read(path).ok();
+        let bytes = crate::docket::encode(&self.to_snapshot(py), previous.as_deref());
+        std::fs::write(path, bytes)
+    }
+
+    /// Load a tree previously written by [`InodeTable::dump`].
+    pub fn load(py: Python<'_>, path: &std::path::Path) -> PyResult<(Self, Py<PyDirectory>)> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| pyo3::exceptions::PyOSError::new_err(e.to_string()))?;
+        let snapshot = crate::docket::decode(&bytes)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        Self::from_snapshot(py, snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_table_with_root(py: Python<'_>) -> InodeTable {
+        let mut table = InodeTable::new(1000, 1000, false);
+        table.init_root(py).unwrap();
+        table
+    }
+
+    #[test]
+    fn rename_only_updates_cached_fields_for_the_dentry_being_renamed() {
+        pyo3::prepare_freethreaded_python();
+        Python::attach(|py| {
+            let mut table = new_table_with_root(py);
+            let file = Py::new(
+                py,
+                PyFile::new(py, "a.txt".to_string(), Some(b"hi"), 0o644).unwrap(),
+            )
+            .unwrap();
+            let ino = table.insert_file(py, ROOT_INO, file).unwrap();
+
+            // A second dentry for the same inode, created via `link`.
+            table.link(py, ino, ROOT_INO, "b.txt").unwrap();
+            assert_eq!(table.nlink(ino), 2);
+
+            // Renaming the *other* dentry ("b.txt") must not touch the
+            // cached name/parent_ino, which still track "a.txt".
+            table
+                .rename(py, ROOT_INO, "b.txt", ROOT_INO, "c.txt")
+                .unwrap();
+            let cached_name = match table.inodes.get(&ino) {
+                Some(NodeRef::File(f)) => f.borrow(py).name.clone(),
+                _ => panic!("expected a file node"),
+            };
+            assert_eq!(cached_name, "a.txt");
+
+            // Renaming the dentry the fields *do* track updates them.
+            table
+                .rename(py, ROOT_INO, "a.txt", ROOT_INO, "renamed.txt")
+                .unwrap();
+            let cached_name = match table.inodes.get(&ino) {
+                Some(NodeRef::File(f)) => f.borrow(py).name.clone(),
+                _ => panic!("expected a file node"),
+            };
+            assert_eq!(cached_name, "renamed.txt");
+        });
+    }
+
+    #[test]
+    fn forget_only_drops_an_unlinked_node_once_lookup_count_reaches_zero() {
+        pyo3::prepare_freethreaded_python();
+        Python::attach(|py| {
+            let mut table = new_table_with_root(py);
+            let file = Py::new(py, PyFile::new(py, "a.txt".to_string(), Some(b"hi"), 0o644).unwrap())
+                .unwrap();
+            let ino = table.insert_file(py, ROOT_INO, file).unwrap();
+
+            // Two outstanding kernel lookups, then the last dentry is unlinked.
+            table.note_lookup(ino);
+            table.note_lookup(ino);
+            table.unlinked.insert(ino);
+
+            table.forget(py, ino, 1);
+            assert!(
+                table.inodes.contains_key(&ino),
+                "node must survive while a lookup reference remains"
+            );
+
+            table.forget(py, ino, 1);
+            assert!(
+                !table.inodes.contains_key(&ino),
+                "node must be dropped once its lookup count reaches zero"
+            );
+        });
+    }
 }
@@ -0,0 +1,113 @@
+//! Stack-based recursive directory walk over an [`InodeTable`], modeled on
+//! diskit's `walkdir`: a node that can't be read (or has since vanished)
+//! fuses that one branch instead of aborting the rest of the walk, and
+//! symlinks are only descended into when explicitly requested, to avoid
+//! cycles.
+use crate::import::glob_match;
+use crate::tree::{Ino, InodeTable, NodeRef, ROOT_INO};
+use pyo3::prelude::*;
+use std::collections::HashSet;
+
+/// One `(dirpath, dirnames, filenames)` entry, matching `os.walk`'s shape.
+pub struct WalkEntry {
+    pub path: String,
+    pub dirnames: Vec<String>,
+    pub filenames: Vec<String>,
+}
+
+/// Walk the tree rooted at `start` (inclusive), top-down, yielding one
+/// `WalkEntry` per directory. A symlink to a directory is only descended
+/// into when `follow_symlinks` is set; a `visited` set guards against the
+/// cycles that following symlinks can introduce.
+pub fn walk(py: Python<'_>, inodes: &InodeTable, start: Ino, start_path: String, follow_symlinks: bool) -> Vec<WalkEntry> {
+    let mut out = Vec::new();
+    let mut visited = HashSet::new();
+    let mut stack = vec![(start, start_path)];
+
+    while let Some((ino, path)) = stack.pop() {
+        if !visited.insert(ino) {
+            continue;
+        }
+        let Some(dir) = inodes.get_dir(ino) else {
+            continue;
+        };
+
+        let mut children: Vec<(String, Ino)> = dir.borrow(py).children.iter().map(|(n, i)| (n.clone(), *i)).collect();
+        children.sort();
+
+        let mut dirnames = Vec::new();
+        let mut filenames = Vec::new();
+        let mut to_descend = Vec::new();
+        for (name, child_ino) in &children {
+            match inodes.get(*child_ino) {
+                Some(NodeRef::Dir(_)) => {
+                    dirnames.push(name.clone());
+                    to_descend.push((name.clone(), *child_ino));
+                }
+                Some(NodeRef::Symlink(s)) => {
+                    let target = s.borrow(py).target.clone();
+                    match follow_symlinks.then(|| resolve_symlink(py, inodes, ino, &target)).flatten() {
+                        Some(target_ino) if inodes.get_dir(target_ino).is_some() => {
+                            dirnames.push(name.clone());
+                            to_descend.push((name.clone(), target_ino));
+                        }
+                        _ => filenames.push(name.clone()),
+                    }
+                }
+                Some(_) => filenames.push(name.clone()),
+                None => {}
+            }
+        }
+
+        for (child_name, child_ino) in to_descend.into_iter().rev() {
+            let child_path = if path.is_empty() {
+                child_name
+            } else {
+                format!("{}/{}", path, child_name)
+            };
+            stack.push((child_ino, child_path));
+        }
+
+        out.push(WalkEntry { path, dirnames, filenames });
+    }
+
+    out
+}
+
+/// Resolve a symlink's target text to an inode, relative to `parent_ino` if
+/// it doesn't start with `/`. Targets containing `..` aren't resolved and
+/// yield `None`.
+fn resolve_symlink(py: Python<'_>, inodes: &InodeTable, parent_ino: Ino, target: &str) -> Option<Ino> {
+    let mut current = if target.starts_with('/') { ROOT_INO } else { parent_ino };
+    for part in target.trim_matches('/').split('/').filter(|s| !s.is_empty()) {
+        if part == ".." {
+            return None;
+        }
+        if part == "." {
+            continue;
+        }
+        current = inodes.lookup(py, current, part)?;
+    }
+    Some(current)
+}
+
+/// All paths under the tree matching a shell glob `pattern` (`*`/`?` match
+/// within a path segment, `**` matches any number of segments).
+pub fn glob(py: Python<'_>, inodes: &InodeTable, pattern: &str) -> Vec<String> {
+    let pattern = pattern.trim_matches('/');
+    let mut matches = Vec::new();
+    for entry in walk(py, inodes, ROOT_INO, String::new(), false) {
+        for name in entry.dirnames.iter().chain(entry.filenames.iter()) {
+            let full = if entry.path.is_empty() {
+                name.clone()
+            } else {
+                format!("{}/{}", entry.path, name)
+            };
+            if glob_match(pattern, &full) {
+                matches.push(full);
+            }
+        }
+    }
+    matches.sort();
+    matches
+}